@@ -1,7 +1,8 @@
 use axum::{
     response::IntoResponse,
     routing::{post, get, Router},
-    extract::{Multipart, State},
+    extract::{Multipart, Path as AxumPath, Query, State},
+    body::Body,
     extract::{ws::{WebSocketUpgrade, Message, WebSocket}},
     http::Method,
     Json,
@@ -27,8 +28,18 @@ mod main_process {
 }
 use main_process::main_processer::NodeManager;
 use main_process::main_processer::ProcessedNode;
+use main_process::main_processer::port_key;
 
 mod nodes;
+use nodes::dialect::{self, Dialect, DialectOverride};
+use nodes::spreadsheet::{self, SpreadsheetOptions};
+use nodes::columnar;
+use nodes::json_import::{self, JsonImportOptions};
+use nodes::db_source;
+use nodes::compressed;
+use nodes::chunked_upload::{is_path_component, ChunkedUpload, UploadMessage};
+use nodes::export::{self, ExportFormat};
+use nodes::preview::{self, Filter, FilterOp, PreviewOptions};
 
 use sqlx::{decode, sqlite::SqlitePool};
 use sqlx::{MySqlPool, mysql::MySqlQueryResult};
@@ -36,7 +47,9 @@ use sqlx::query;
 use axum_extra::extract::cookie::{Cookie, CookieJar};
 use rand::prelude::*;
 mod verification_custom;
-use http::header::{AUTHORIZATION, CONTENT_TYPE, COOKIE};
+use http::header::{HeaderName, AUTHORIZATION, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE};
+use tokio_util::io::ReaderStream;
+use std::time::SystemTime;
 use tokio::task;
 use tokio::fs::File;
 use tokio::spawn;
@@ -46,8 +59,21 @@ use base64::Engine;
 lazy_static! {
     static ref FILE_STORE: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
     static ref NODE_DICT: RwLock<HashMap<u32, NodePayload>> = RwLock::new(HashMap::new());
+    static ref RUNS: RwLock<Vec<RunRecord>> = RwLock::new(Vec::new());
 }
 
+// Outputs of a finished `/process-nodes` run, so they can be fetched after the response was sent.
+// Nodes write to fixed paths, a later run of the same node replaces the file.
+#[derive(Clone)]
+struct RunRecord {
+    run_id: String,
+    outputs: HashMap<String, String>,
+    finished: SystemTime,
+}
+
+const KEPT_RUNS: usize = 20;
+const RUN_ID_HEADER: &str = "x-run-id";
+
 pub type FileStore = HashMap<String, String>;
 pub type NodeDict = HashMap<u32, NodePayload>;
 #[derive(Clone)]
@@ -118,6 +144,10 @@ struct FilePayload {
     file_name: String,
     file_type: String,
     file_data: String,  // Base64 encoded file content
+    #[serde(default)]
+    spreadsheet: Option<SpreadsheetOptions>, // Sheet and range to import from xlsx/xls uploads
+    #[serde(default)]
+    json: Option<JsonImportOptions>, // Array handling and record path for JSON/NDJSON uploads
 }
 
 impl PartialEq for NodePayload {
@@ -273,12 +303,20 @@ async fn websocket_upgrade(ws: WebSocketUpgrade, jar: CookieJar, State(pool): St
 }
 async fn handle_socket(mut socket: WebSocket, session: SessionData, email: String) {
     println!("✅ WebSocket connection established");
+    // Chunked upload in progress on this connection, with the import settings from its `begin`
+    let mut upload: Option<(ChunkedUpload, ImportOptions)> = None;
 
     while let Some(Ok(msg)) = socket.next().await {
         match msg {
             Message::Text(text) => {
                 println!("📩 Received WebSocket message: {}", text); // Debug: Log incoming messages
 
+                if let Ok(message) = serde_json::from_str::<UploadMessage>(&text) {
+                    let reply = handle_upload_message(message, &mut upload, &email).await;
+                    let _ = socket.send(Message::Text(reply.to_string().into())).await;
+                    continue;
+                }
+
                 // Try parsing as `NodePayload`
                 match serde_json::from_str::<Vec<NodePayload>>(&text) {
                     Ok(nodes) => {
@@ -299,6 +337,11 @@ async fn handle_socket(mut socket: WebSocket, session: SessionData, email: Strin
                         println!("✅ Parsed as FilePayload");
                         println!("📂 Received file: {} (ID: {})", file_payload.file_name, file_payload.id);
 
+                        // Both end up in the path, neither may leave the user's upload directory
+                        if !is_path_component(&file_payload.id) || !is_path_component(&file_payload.file_name) {
+                            println!("❌ Invalid upload id '{}' or file name '{}'", file_payload.id, file_payload.file_name);
+                            continue;
+                        }
                         let dir_path = format!("./uploads/{}/{}/", email, file_payload.id); // TODO: Also add the project_id the user is current on 
                         let file_path = format!("{}/{}", dir_path, file_payload.file_name);
                         
@@ -313,6 +356,23 @@ async fn handle_socket(mut socket: WebSocket, session: SessionData, email: Strin
                                     Ok(mut file) => {
                                         if let Ok(_) = file.write_all(&decoded_bytes).await {
                                             println!("✅ File saved at {}", file_path);
+                                            let _ = file.flush().await;
+                                            let options = ImportOptions {
+                                                spreadsheet: file_payload.spreadsheet,
+                                                json: file_payload.json,
+                                                dialect: None,
+                                            };
+                                            let importing = file_path.clone();
+                                            match task::spawn_blocking(move || import_upload(&importing, &options)).await {
+                                                Ok(Ok(imported)) => {
+                                                    register_upload(&file_payload.id, &imported).await;
+                                                    for (member, csv_path, _) in imported {
+                                                        println!("✅ Imported {} as {}", member.unwrap_or(file_path.clone()), csv_path);
+                                                    }
+                                                }
+                                                Ok(Err(err)) => println!("❌ Failed to import {}: {}", file_path, err),
+                                                Err(err) => println!("❌ Failed to import {}: {}", file_path, err),
+                                            }
                                         } else {
                                             println!("❌ Failed to write file to disk");
                                         }
@@ -332,6 +392,16 @@ async fn handle_socket(mut socket: WebSocket, session: SessionData, email: Strin
                     }
                 }
             }
+            Message::Binary(chunk) => {
+                let reply = match upload.as_mut() {
+                    Some((active, _)) => match active.write_chunk(&chunk).await {
+                        Ok(()) => active.progress(),
+                        Err(err) => upload_error(Some(&active.upload_id), err.to_string()),
+                    },
+                    None => upload_error(None, "No upload in progress, send `begin` first".to_string()),
+                };
+                let _ = socket.send(Message::Text(reply.to_string().into())).await;
+            }
             Message::Close(_) => {
                 println!("❌ Client disconnected");
                 return;
@@ -343,6 +413,70 @@ async fn handle_socket(mut socket: WebSocket, session: SessionData, email: Strin
     }
 }
 
+fn upload_error(upload_id: Option<&str>, message: String) -> serde_json::Value {
+    json!({ "upload": "error", "upload_id": upload_id, "message": message })
+}
+
+// `begin` starts or resumes an upload (the reply says which chunk to send next), `commit` verifies
+// and imports the file, `abort` throws the partial file away
+async fn handle_upload_message(message: UploadMessage, upload: &mut Option<(ChunkedUpload, ImportOptions)>, email: &str) -> serde_json::Value {
+    match message {
+        UploadMessage::Begin(begin) => {
+            let root = format!("./uploads/{}", email);
+            // Release the file of an earlier upload on this connection, it may be the one begun again
+            upload.take();
+            match ChunkedUpload::begin(&root, &begin).await {
+                Ok(started) => {
+                    let reply = started.progress();
+                    let options = ImportOptions { spreadsheet: begin.spreadsheet, json: begin.json, dialect: None };
+                    *upload = Some((started, options));
+                    reply
+                }
+                Err(err) => upload_error(None, err.to_string()),
+            }
+        }
+        UploadMessage::Commit => {
+            // An early commit keeps the upload going, the client can still send the missing chunks
+            match upload.as_ref() {
+                None => return upload_error(None, "No upload in progress".to_string()),
+                Some((active, _)) if !active.is_complete() => {
+                    return upload_error(Some(&active.upload_id), format!("Upload incomplete, next chunk is {}", active.next_chunk()));
+                }
+                Some(_) => {}
+            }
+            let Some((active, options)) = upload.take() else {
+                return upload_error(None, "No upload in progress".to_string());
+            };
+            let (id, upload_id) = (active.id.clone(), active.upload_id.clone());
+            let file_path = match active.commit().await {
+                Ok(file_path) => file_path,
+                Err(err) => return upload_error(Some(&upload_id), err.to_string()),
+            };
+            let importing = file_path.clone();
+            match task::spawn_blocking(move || import_upload(&importing, &options)).await {
+                Ok(Ok(imported)) => {
+                    register_upload(&id, &imported).await;
+                    let files: Vec<serde_json::Value> = imported
+                        .iter()
+                        .map(|(member, csv_path, details)| json!({ "port": member, "path": csv_path, "details": details }))
+                        .collect();
+                    json!({ "upload": "complete", "upload_id": upload_id, "id": id, "path": file_path, "files": files })
+                }
+                Ok(Err(err)) => upload_error(Some(&upload_id), err),
+                Err(err) => upload_error(Some(&upload_id), err.to_string()),
+            }
+        }
+        UploadMessage::Abort => match upload.take() {
+            Some((active, _)) => {
+                let upload_id = active.upload_id.clone();
+                active.abort().await;
+                json!({ "upload": "aborted", "upload_id": upload_id })
+            }
+            None => upload_error(None, "No upload in progress".to_string()),
+        },
+    }
+}
+
 async fn add_to_node_store(session: &SessionData, key: u32, value: NodePayload) {
     let mut node_lock = session.node_dict.write().await;
     
@@ -359,22 +493,81 @@ async fn add_to_file_store(key: String, value: String) {
     store_lock.insert(key, value);
 }
 
+// The first file is the main output of the upload, files of an archive are also its ports (see `port_key`).
+// Returns the archive files for the reply.
+async fn register_upload(id: &str, imported: &[(Option<String>, String, serde_json::Value)]) -> Vec<serde_json::Value> {
+    let mut files: Vec<serde_json::Value> = Vec::new();
+    for (index, (member, csv_path, _)) in imported.iter().enumerate() {
+        if index == 0 {
+            add_to_file_store(id.to_string(), csv_path.clone()).await;
+        }
+        if let Some(member) = member {
+            add_to_file_store(format!("{}:{}", id, member), csv_path.clone()).await;
+            files.push(json!({ "port": member, "path": csv_path }));
+        }
+    }
+    files
+}
+
 async fn upload_csv(mut multipart: Multipart) -> impl IntoResponse {
     let mut id: Option<String> = None;
     let mut file_path: Option<String> = None;
+    let mut options = ImportOptions::default();
 
     while let Some(field) = multipart.next_field().await.unwrap() {
         if let Some(name) = field.name() {
             if name == "id" {
                 id = Some(field.text().await.unwrap());
+            } else if name == "dialect" {
+                // Optional JSON with any of `delimiter`, `quote`, `has_headers`, `encoding`
+                match serde_json::from_str(&field.text().await.unwrap()) {
+                    Ok(parsed) => options.dialect = Some(parsed),
+                    Err(err) => {
+                        return Json(json!({
+                            "status": "error",
+                            "message": format!("Invalid dialect: {}", err)
+                        }));
+                    }
+                }
+            } else if name == "spreadsheet" {
+                // Optional JSON with `sheet`, `range` and `header_row` for xlsx/xls uploads
+                match serde_json::from_str(&field.text().await.unwrap()) {
+                    Ok(parsed) => options.spreadsheet = Some(parsed),
+                    Err(err) => {
+                        return Json(json!({
+                            "status": "error",
+                            "message": format!("Invalid spreadsheet options: {}", err)
+                        }));
+                    }
+                }
+            } else if name == "json" {
+                // Optional JSON with `arrays` (`serialize` or `explode`), `records_path` and `separator` for JSON/NDJSON uploads
+                match serde_json::from_str(&field.text().await.unwrap()) {
+                    Ok(parsed) => options.json = Some(parsed),
+                    Err(err) => {
+                        return Json(json!({
+                            "status": "error",
+                            "message": format!("Invalid JSON import options: {}", err)
+                        }));
+                    }
+                }
             } else if name == "file" {
                 if let Some(file_name) = field.file_name() {
                     let path = format!("./uploads/{}", file_name);
-                    let bytes = field.bytes().await.unwrap();
 
-                    // Write file to disk
-                    if let Ok(_) = tokio::fs::write(&path, bytes).await {
-                        file_path = Some(path);
+                    // Write file to disk chunk by chunk, large and compressed uploads never sit in memory whole
+                    if let Ok(mut file) = File::create(&path).await {
+                        let mut field = field;
+                        let mut saved = true;
+                        while let Ok(Some(chunk)) = field.chunk().await {
+                            if file.write_all(&chunk).await.is_err() {
+                                saved = false;
+                                break;
+                            }
+                        }
+                        if saved && file.flush().await.is_ok() {
+                            file_path = Some(path);
+                        }
                     }
                 }
             }
@@ -382,15 +575,37 @@ async fn upload_csv(mut multipart: Multipart) -> impl IntoResponse {
     }
 
     if let (Some(id), Some(file_path)) = (id, file_path) {
-        // Store in the global dictionary using the helper function
-        add_to_file_store(id.clone(), file_path.clone()).await;
-    
+        // Blocking work, and Box<dyn Error> is not Send, so errors come back as strings
+        let imported = match task::spawn_blocking(move || import_upload(&file_path, &options)).await {
+            Ok(Ok(imported)) => imported,
+            Ok(Err(err)) => {
+                return Json(json!({
+                    "status": "error",
+                    "message": err
+                }));
+            }
+            Err(err) => {
+                return Json(json!({
+                    "status": "error",
+                    "message": err.to_string()
+                }));
+            }
+        };
+
+        let files = register_upload(&id, &imported).await;
+
         // Return success response
-        return Json(json!({
+        let (_, file_path, details) = &imported[0];
+        let mut response = json!({
             "status": "success",
             "message": format!("File saved to {}", file_path),
-            "id": id
-        }));
+            "id": id,
+            "files": files
+        });
+        if let (Some(response), Some(details)) = (response.as_object_mut(), details.as_object()) {
+            response.extend(details.clone());
+        }
+        return Json(response);
     }
     
 
@@ -400,6 +615,146 @@ async fn upload_csv(mut multipart: Multipart) -> impl IntoResponse {
     }));
 }
 
+// Settings sent along with an upload, each applies to the files it fits
+#[derive(Default)]
+struct ImportOptions {
+    spreadsheet: Option<SpreadsheetOptions>,
+    json: Option<JsonImportOptions>,
+    dialect: Option<DialectOverride>,
+}
+
+// Expand compressed uploads, then import every resulting file. Each entry is the name inside an
+// archive (none for plain and single-file compressed uploads), the CSV nodes read and the import details.
+fn import_upload(file_path: &str, options: &ImportOptions) -> Result<Vec<(Option<String>, String, serde_json::Value)>, String> {
+    if !compressed::is_compressed(file_path) {
+        let (csv_path, details) = import_file(file_path, options)?;
+        return Ok(vec![(None, csv_path, details)]);
+    }
+    let archive = compressed::is_archive(file_path);
+    let expanded = compressed::decompress(file_path).map_err(|err| format!("Could not decompress {}: {}", file_path, err))?;
+    expanded
+        .into_iter()
+        .map(|(name, path)| {
+            let (csv_path, details) = import_file(&path, options)?;
+            Ok((archive.then_some(name), csv_path, details))
+        })
+        .collect()
+}
+
+// Spreadsheets, Parquet, Arrow and JSON files are converted to CSV once, nodes then read the converted table;
+// CSVs get their dialect sniffed. Returns the CSV path and what was done to it.
+fn import_file(file_path: &str, options: &ImportOptions) -> Result<(String, serde_json::Value), String> {
+    let mut sheet_info = None;
+    let mut columnar_info = None;
+    let mut json_info = None;
+    let converted = if spreadsheet::is_spreadsheet(file_path) {
+        let defaults = SpreadsheetOptions::default();
+        spreadsheet::convert_to_csv(file_path, options.spreadsheet.as_ref().unwrap_or(&defaults)).map(|(path, info)| {
+            sheet_info = Some(info);
+            Some(path)
+        })
+    } else if columnar::is_columnar(file_path) {
+        columnar::convert_to_csv(file_path).map(|(path, info)| {
+            columnar_info = Some(info);
+            Some(path)
+        })
+    } else if json_import::is_json(file_path) {
+        let defaults = JsonImportOptions::default();
+        json_import::convert_to_csv(file_path, options.json.as_ref().unwrap_or(&defaults)).map(|(path, info)| {
+            json_info = Some(info);
+            Some(path)
+        })
+    } else {
+        Ok(None)
+    }
+    .map_err(|err| format!("Could not import {}: {}", file_path, err))?;
+
+    let sniff = converted.is_none();
+    let csv_path = converted.unwrap_or_else(|| file_path.to_string());
+    // Sniff the dialect, apply what the user set explicitly and store it next to the file
+    let dialect = detect_dialect(&csv_path, options.dialect.clone(), sniff).map_err(|err| format!("Could not read {}: {}", csv_path, err))?;
+    Ok((
+        csv_path,
+        json!({
+            "dialect": dialect,
+            "spreadsheet": sheet_info,
+            "columnar": columnar_info,
+            "json": json_info
+        }),
+    ))
+}
+
+fn detect_dialect(file_path: &str, dialect_override: Option<DialectOverride>, sniff: bool) -> Result<Dialect, Box<dyn std::error::Error>> {
+    let mut dialect = if sniff { dialect::detect_and_save(file_path)? } else { dialect::load(file_path) };
+    if let Some(changes) = dialect_override {
+        dialect.apply(changes)?;
+        dialect::save(file_path, &dialect)?;
+    }
+    Ok(dialect)
+}
+
+// Correct the sniffed dialect of an uploaded file, nodes pick it up on the next run
+async fn update_dialect(AxumPath(id): AxumPath<String>, Json(changes): Json<DialectOverride>) -> impl IntoResponse {
+    let file_path = match FILE_STORE.read().await.get(&id) {
+        Some(path) => path.clone(),
+        None => {
+            return Json(json!({
+                "status": "error",
+                "message": format!("No file with id {}", id)
+            }));
+        }
+    };
+
+    let mut dialect = dialect::load(&file_path);
+    if let Err(err) = dialect.apply(changes).and_then(|_| dialect::save(&file_path, &dialect)) {
+        return Json(json!({
+            "status": "error",
+            "message": err.to_string()
+        }));
+    }
+    Json(json!({
+        "status": "success",
+        "id": id,
+        "dialect": dialect
+    }))
+}
+
+// Import a different sheet or range of an uploaded workbook under the same file id
+async fn update_spreadsheet(AxumPath(id): AxumPath<String>, Json(options): Json<SpreadsheetOptions>) -> impl IntoResponse {
+    let source = match FILE_STORE.read().await.get(&id) {
+        Some(path) => spreadsheet::source_path(path),
+        None => {
+            return Json(json!({
+                "status": "error",
+                "message": format!("No file with id {}", id)
+            }));
+        }
+    };
+    let Some(source) = source else {
+        return Json(json!({
+            "status": "error",
+            "message": format!("File {} was not uploaded as a spreadsheet", id)
+        }));
+    };
+
+    // Keep the non-Send error out of the await below
+    let converted = spreadsheet::convert_to_csv(&source, &options).map_err(|err| err.to_string());
+    match converted {
+        Ok((csv_path, info)) => {
+            add_to_file_store(id.clone(), csv_path).await;
+            Json(json!({
+                "status": "success",
+                "id": id,
+                "spreadsheet": info
+            }))
+        }
+        Err(err) => Json(json!({
+            "status": "error",
+            "message": err
+        })),
+    }
+}
+
 async fn handle_node(node: NodePayload, session: &SessionData,) {
     println!(
         "Received payload: ID = {}, Type = {}, Neighbors Dependent = {:?}, Neighbors Pointing = {:?}, Data = {}",
@@ -483,11 +838,221 @@ async fn process_nodes() -> impl IntoResponse {
 
     let copied_node_dict: HashMap<u32, NodePayload> = deep_copy_node_dict().await;
     let mut copied_file_dict: HashMap<String, String> = deep_copy_file_dict().await;
-    let mut manager = NodeManager::new(results, &copied_node_dict, &mut copied_file_dict);
-    
-    manager.print_state();
-    let defults = manager.process_nodes_in_order(); // Extract Vec<ProcessedNode> from Json
-    defults
+    // Nodes do blocking file and database work, run them off the async workers. The manager holds
+    // `dyn Any` nodes, which are not Send, so it is built on the blocking thread as well.
+    let processed = task::spawn_blocking(move || {
+        let mut manager = NodeManager::new(results, &copied_node_dict, &mut copied_file_dict);
+
+        manager.print_state();
+        let defults = manager.process_nodes_in_order(); // Extract Vec<ProcessedNode> from Json
+        (defults, copied_file_dict)
+    })
+    .await;
+    let (defults, copied_file_dict) = match processed {
+        Ok(processed) => processed,
+        Err(err) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    };
+    let run_id = record_run(copied_file_dict).await;
+    // The body stays the list of node results, the run id travels in a header
+    ([(RUN_ID_HEADER, run_id)], defults).into_response()
+}
+
+async fn record_run(outputs: HashMap<String, String>) -> String {
+    let run_id = chrono::Utc::now().format("%Y%m%d%H%M%S%3f").to_string();
+    let mut runs = RUNS.write().await;
+    runs.push(RunRecord { run_id: run_id.clone(), outputs, finished: SystemTime::now() });
+    if runs.len() > KEPT_RUNS {
+        runs.remove(0);
+    }
+    run_id
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "status": "error", "message": message }))).into_response()
+}
+
+// File behind a node output of a run, `latest` names the most recent run
+async fn resolve_output(run_id: &str, node_id: &str, port: &str) -> Result<(RunRecord, String), Response> {
+    let run = {
+        let runs = RUNS.read().await;
+        let found = if run_id == "latest" { runs.last() } else { runs.iter().find(|r| r.run_id == run_id) };
+        found.cloned()
+    };
+    let run = run.ok_or_else(|| error_response(StatusCode::NOT_FOUND, format!("Run {} not found", run_id)))?;
+    let key = match node_id.parse::<u32>() {
+        Ok(id) => port_key(id, port),
+        Err(_) => node_id.to_string(), // Uploaded files are keyed by their upload id
+    };
+    let path = run
+        .outputs
+        .get(&key)
+        .cloned()
+        .ok_or_else(|| error_response(StatusCode::NOT_FOUND, format!("Node {} has no output in run {}", key, run.run_id)))?;
+    let modified = fs::metadata(&path)
+        .await
+        .and_then(|m| m.modified())
+        .map_err(|err| error_response(StatusCode::NOT_FOUND, format!("Output of node {} is gone: {}", key, err)))?;
+    if modified > run.finished {
+        return Err(error_response(StatusCode::CONFLICT, format!("Output of node {} was replaced by a later run", key)));
+    }
+    Ok((run, path))
+}
+
+#[derive(Deserialize)]
+struct DownloadQuery {
+    #[serde(default = "default_download_format")]
+    format: String,
+    #[serde(default)]
+    port: String, // Another output of the node, e.g. `test` of a split or `parquet`
+}
+
+fn default_download_format() -> String {
+    "csv".to_string()
+}
+
+#[derive(Deserialize)]
+struct PreviewQuery {
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    limit: Option<usize>,
+    #[serde(default)]
+    sort: Option<String>,
+    #[serde(default)]
+    order: Option<String>, // `asc` (default) or `desc`
+    #[serde(default)]
+    filter_column: Option<String>,
+    #[serde(default)]
+    filter_op: Option<FilterOp>,
+    #[serde(default)]
+    filter_value: Option<String>,
+    #[serde(default)]
+    format: Option<String>, // `ndjson` streams every matching row instead of one page
+    #[serde(default)]
+    port: String,
+}
+
+const PREVIEW_DEFAULT_ROWS: usize = 100;
+const PREVIEW_MAX_ROWS: usize = 1000;
+
+async fn preview_output(AxumPath((run_id, node_id)): AxumPath<(String, String)>, Query(params): Query<PreviewQuery>) -> Response {
+    let streaming = match params.format.as_deref() {
+        None | Some("json") => false,
+        Some("ndjson") => true,
+        Some(other) => return error_response(StatusCode::BAD_REQUEST, format!("Unknown format '{}', use json or ndjson", other)),
+    };
+    let descending = match params.order.as_deref() {
+        None | Some("asc") => false,
+        Some("desc") => true,
+        Some(other) => return error_response(StatusCode::BAD_REQUEST, format!("Unknown order '{}', use asc or desc", other)),
+    };
+    let filter = match (params.filter_column, params.filter_op) {
+        (Some(column), Some(op)) => Some(Filter { column, op, value: params.filter_value.unwrap_or_default() }),
+        (None, None) => None,
+        _ => return error_response(StatusCode::BAD_REQUEST, "Set both filter_column and filter_op".to_string()),
+    };
+    let limit = if streaming { params.limit } else { Some(params.limit.unwrap_or(PREVIEW_DEFAULT_ROWS).min(PREVIEW_MAX_ROWS)) };
+    let options = PreviewOptions { offset: params.offset, limit, sort: params.sort, descending, filter };
+
+    let (run, source) = match resolve_output(&run_id, &node_id, &params.port).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+    // Parquet outputs are previewed through their CSV conversion
+    let prepared = task::spawn_blocking(move || {
+        let prepare = || {
+            let source = if columnar::is_columnar(&source) { columnar::convert_to_csv(&source)?.0 } else { source };
+            preview::check_columns(&source, &options)?;
+            Ok::<_, Box<dyn std::error::Error>>((source, options))
+        };
+        prepare().map_err(|err| err.to_string())
+    })
+    .await;
+    let (source, options) = match prepared {
+        Ok(Ok(prepared)) => prepared,
+        Ok(Err(err)) => return error_response(StatusCode::UNPROCESSABLE_ENTITY, err),
+        Err(err) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    };
+
+    if streaming {
+        // One JSON object per line, read on a blocking thread and handed over as the client consumes them
+        let (sender, receiver) = tokio::sync::mpsc::channel::<Result<String, std::io::Error>>(256);
+        task::spawn_blocking(move || {
+            let scanned = preview::scan(&source, &options, |row| sender.blocking_send(Ok(format!("{}\n", row))).is_ok());
+            if let Err(err) = scanned {
+                let _ = sender.blocking_send(Err(std::io::Error::other(err.to_string())));
+            }
+        });
+        let rows = futures::stream::unfold(receiver, |mut receiver| async move { receiver.recv().await.map(|row| (row, receiver)) });
+        return ([(CONTENT_TYPE, "application/x-ndjson")], Body::from_stream(rows)).into_response();
+    }
+
+    let offset = options.offset;
+    let page = task::spawn_blocking(move || {
+        let mut rows: Vec<serde_json::Value> = Vec::new();
+        preview::scan(&source, &options, |row| {
+            rows.push(row);
+            true
+        })
+        .map(|(schema, total)| (schema, total, rows))
+        .map_err(|err| err.to_string())
+    })
+    .await;
+    match page {
+        Ok(Ok((schema, total, rows))) => Json(json!({
+            "run_id": run.run_id,
+            "node_id": node_id,
+            "offset": offset,
+            "limit": limit,
+            "total_rows": total,
+            "schema": schema,
+            "rows": rows,
+        }))
+        .into_response(),
+        Ok(Err(err)) => error_response(StatusCode::UNPROCESSABLE_ENTITY, err),
+        Err(err) => error_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+async fn download_output(AxumPath((run_id, node_id)): AxumPath<(String, String)>, Query(params): Query<DownloadQuery>) -> Response {
+    let format = match ExportFormat::parse(&params.format) {
+        Ok(format) => format,
+        Err(err) => return error_response(StatusCode::BAD_REQUEST, err),
+    };
+    let (run, source) = match resolve_output(&run_id, &node_id, &params.port).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    let name = export::download_name(&node_id, &params.port, format);
+    let output_file = export::export_path(&run.run_id, &node_id, &params.port, format);
+    // Conversions read the whole table, keep them off the async workers
+    let exported = task::spawn_blocking(move || export::export(&source, format, &output_file).map_err(|err| err.to_string())).await;
+    let path = match exported {
+        Ok(Ok(path)) => path,
+        Ok(Err(err)) => return error_response(StatusCode::UNPROCESSABLE_ENTITY, err),
+        Err(err) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    };
+
+    let file = match File::open(&path).await {
+        Ok(file) => file,
+        Err(err) => return error_response(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    };
+    let length = file.metadata().await.map(|m| m.len()).unwrap_or_default();
+    (
+        [
+            (CONTENT_TYPE, format.content_type().to_string()),
+            (CONTENT_LENGTH, length.to_string()),
+            (CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", name)),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    )
+        .into_response()
+}
+
+// Connections a `db-source` node can name, credentials stay on the server
+async fn list_db_connections() -> impl IntoResponse {
+    Json(json!({ "connections": db_source::list_connections() }))
 }
 
 #[tokio::main]
@@ -498,6 +1063,7 @@ async fn main() {
     .allow_origin("http://localhost:5173".parse::<axum::http::HeaderValue>().unwrap()) // Allow your frontend origin
     .allow_methods([Method::GET, Method::POST]) // Allow specific HTTP methods
     .allow_headers([CONTENT_TYPE, AUTHORIZATION, COOKIE]) // Allow all headers (or specify the ones you need)
+    .expose_headers([HeaderName::from_static(RUN_ID_HEADER)]) // Let the frontend read the run id of `/process-nodes`
     .allow_credentials(true); // Allow cookies
     
     // Ensure the upload directory exists
@@ -534,6 +1100,11 @@ async fn main() {
     // Define Route
     let app = Router::new()
         .route("/upload-csv", post(upload_csv))
+        .route("/files/{id}/dialect", post(update_dialect))
+        .route("/files/{id}/spreadsheet", post(update_spreadsheet))
+        .route("/db-connections", get(list_db_connections))
+        .route("/runs/{run_id}/nodes/{node_id}/download", get(download_output))
+        .route("/runs/{run_id}/nodes/{node_id}/preview", get(preview_output))
         .route("/process-nodes", post(process_nodes))
         .route("/signup", post(register_user))
         .route("/login", post(login_user))
@@ -3,10 +3,29 @@ use crate::NodePayload;
 use std::any::Any;
 use axum::Json;
 use serde::Serialize;
+use serde_json::json;
 
 use crate::nodes::inner_join::Inner_Join;
 use crate::nodes::clean_na::Clean_By_Column;
 use crate::nodes::output_csv::Output_CSV;
+use crate::nodes::linear_regression::Linear_Regression;
+use crate::nodes::logistic_regression::Logistic_Regression;
+use crate::nodes::correlation::Correlation_Matrix;
+use crate::nodes::histogram::Histogram;
+use crate::nodes::outliers::Outlier_Detection;
+use crate::nodes::transform::Column_Transform;
+use crate::nodes::time_series::Time_Series;
+use crate::nodes::window_functions::Window_Functions;
+use crate::nodes::sql_query::Sql_Query;
+use crate::nodes::kmeans::K_Means;
+use crate::nodes::pca::Principal_Components;
+use crate::nodes::survival::Kaplan_Meier;
+use crate::nodes::resampling::Resampling;
+use crate::nodes::sample::Sample_Rows;
+use crate::nodes::text_transform::Text_Transform;
+use crate::nodes::cast_columns::Cast_Columns;
+use crate::nodes::output_parquet::Output_Parquet;
+use crate::nodes::db_source::Db_Source;
 
 pub struct NodeManager<'a> {
     pub numbers: Vec<u32>,
@@ -21,6 +40,42 @@ pub struct ProcessedNode {
     pub data: serde_json::Value, 
 }
 
+// Key of a node output in `file_dict`, the main output is stored under the bare node id
+pub fn port_key(node_id: u32, port: &str) -> String {
+    if port.is_empty() || port == "main" {
+        node_id.to_string()
+    } else {
+        format!("{}:{}", node_id, port)
+    }
+}
+
+// Inputs are read from the main output unless the node data picks another port, e.g. `"input_ports": {"4": "test"}`
+fn input_keys(neighbors_dependent: &[u32], node_data: &str) -> Vec<String> {
+    let ports = serde_json::from_str::<serde_json::Value>(node_data)
+        .ok()
+        .and_then(|data| data.get("input_ports").cloned());
+    neighbors_dependent
+        .iter()
+        .map(|id| {
+            let port = ports
+                .as_ref()
+                .and_then(|p| p.get(id.to_string()))
+                .and_then(|p| p.as_str())
+                .unwrap_or("");
+            port_key(*id, port)
+        })
+        .collect()
+}
+
+// Report a failed node to the client instead of aborting the whole run
+fn error_node(node_id: u32, err: Box<dyn std::error::Error>) -> ProcessedNode {
+    println!("Node {} failed: {}", node_id, err);
+    ProcessedNode {
+        node_id,
+        data: json!({ "error": err.to_string() }),
+    }
+}
+
 impl<'a> NodeManager<'a> {
     pub fn new(numbers: Vec<u32>, node_dict: &'a HashMap<u32, NodePayload>, file_dict: &'a mut HashMap<String, String>) -> Self {
         let mut node_map = HashMap::new();
@@ -35,6 +90,42 @@ impl<'a> NodeManager<'a> {
                     node_map.insert(node_type.clone(), Box::new(Clean_By_Column) as Box<dyn Any>);
                 } else if node_type == "output-to-csv" {
                     node_map.insert(node_type.clone(), Box::new(Output_CSV) as Box<dyn Any>);
+                } else if node_type == "linear-regression" {
+                    node_map.insert(node_type.clone(), Box::new(Linear_Regression) as Box<dyn Any>);
+                } else if node_type == "logistic-regression" {
+                    node_map.insert(node_type.clone(), Box::new(Logistic_Regression) as Box<dyn Any>);
+                } else if node_type == "correlation-matrix" {
+                    node_map.insert(node_type.clone(), Box::new(Correlation_Matrix) as Box<dyn Any>);
+                } else if node_type == "histogram" {
+                    node_map.insert(node_type.clone(), Box::new(Histogram) as Box<dyn Any>);
+                } else if node_type == "outlier-detection" {
+                    node_map.insert(node_type.clone(), Box::new(Outlier_Detection) as Box<dyn Any>);
+                } else if node_type == "column-transform" {
+                    node_map.insert(node_type.clone(), Box::new(Column_Transform) as Box<dyn Any>);
+                } else if node_type == "time-series" {
+                    node_map.insert(node_type.clone(), Box::new(Time_Series) as Box<dyn Any>);
+                } else if node_type == "window-functions" {
+                    node_map.insert(node_type.clone(), Box::new(Window_Functions) as Box<dyn Any>);
+                } else if node_type == "sql-query" {
+                    node_map.insert(node_type.clone(), Box::new(Sql_Query) as Box<dyn Any>);
+                } else if node_type == "kmeans" {
+                    node_map.insert(node_type.clone(), Box::new(K_Means) as Box<dyn Any>);
+                } else if node_type == "pca" {
+                    node_map.insert(node_type.clone(), Box::new(Principal_Components) as Box<dyn Any>);
+                } else if node_type == "kaplan-meier" {
+                    node_map.insert(node_type.clone(), Box::new(Kaplan_Meier) as Box<dyn Any>);
+                } else if node_type == "resampling" {
+                    node_map.insert(node_type.clone(), Box::new(Resampling) as Box<dyn Any>);
+                } else if node_type == "sample" {
+                    node_map.insert(node_type.clone(), Box::new(Sample_Rows) as Box<dyn Any>);
+                } else if node_type == "text-transform" {
+                    node_map.insert(node_type.clone(), Box::new(Text_Transform) as Box<dyn Any>);
+                } else if node_type == "cast-columns" {
+                    node_map.insert(node_type.clone(), Box::new(Cast_Columns) as Box<dyn Any>);
+                } else if node_type == "output-to-parquet" {
+                    node_map.insert(node_type.clone(), Box::new(Output_Parquet) as Box<dyn Any>);
+                } else if node_type == "db-source" {
+                    node_map.insert(node_type.clone(), Box::new(Db_Source) as Box<dyn Any>);
                 } else {
                     continue;
                 }
@@ -56,23 +147,187 @@ impl<'a> NodeManager<'a> {
                 if let Some(node) = self.node_map.get(node_type) { // Populate node modules here, if successful from any of these functions, then it shouldn't return any string. 
                     let neighbors_dependent: Vec<u32> = node_payload.neighbors_dependent.clone();
                     let node_data = &node_payload.data;
+                    let input_keys = input_keys(&neighbors_dependent, node_data);
                     if let Some(node) = node.downcast_ref::<Inner_Join>() {
-                        let csv_path1: &String = self.file_dict.get(&neighbors_dependent[0].to_string()).unwrap();
-                        let csv_path2 = self.file_dict.get(&neighbors_dependent[1].to_string()).unwrap();
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        let csv_path2 = self.file_dict.get(&input_keys[1]).unwrap();
                         let return_path = node.process_node(&csv_path1, &csv_path2, &node_data, &node_id).unwrap();
                         self.file_dict.insert(node_id.to_string(), return_path);
                     } else if let Some(node) = node.downcast_ref::<Clean_By_Column>() {
-                        let csv_path1: &String = self.file_dict.get(&neighbors_dependent[0].to_string()).unwrap();
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
                         let return_path = node.process_node(&csv_path1, &node_data, &node_id).unwrap();
                         self.file_dict.insert(node_id.to_string(), return_path);
                     } else if let Some(node) = node.downcast_ref::<Output_CSV>() {
-                        let csv_path1: &String = self.file_dict.get(&neighbors_dependent[0].to_string()).unwrap();
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
                         if let Ok(return_data) = node.process_node(&csv_path1) {
                             results.push(ProcessedNode {
                                 node_id,
                                 data: return_data, 
                             });
                         }
+                    } else if let Some(node) = node.downcast_ref::<Linear_Regression>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                // Without fitted values the input table passes through unchanged
+                                let return_path = return_path.unwrap_or_else(|| csv_path1.clone());
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Logistic_Regression>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                let return_path = return_path.unwrap_or_else(|| csv_path1.clone());
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Correlation_Matrix>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Histogram>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data) {
+                            Ok(summary) => results.push(ProcessedNode { node_id, data: summary }),
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Outlier_Detection>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Column_Transform>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Time_Series>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Window_Functions>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok(return_path) => {
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Sql_Query>() {
+                        let inputs: Vec<(u32, &String)> = neighbors_dependent
+                            .iter()
+                            .zip(input_keys.iter())
+                            .filter_map(|(id, key)| self.file_dict.get(key).map(|path| (*id, path)))
+                            .collect();
+                        match node.process_node(&inputs, node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<K_Means>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Principal_Components>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Kaplan_Meier>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data) {
+                            Ok(summary) => results.push(ProcessedNode { node_id, data: summary }),
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Resampling>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data) {
+                            Ok(summary) => results.push(ProcessedNode { node_id, data: summary }),
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Sample_Rows>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok(output) => {
+                                self.file_dict.insert(node_id.to_string(), output.path);
+                                if let Some(test_path) = output.test_path {
+                                    self.file_dict.insert(port_key(node_id, "test"), test_path);
+                                }
+                                results.push(ProcessedNode { node_id, data: output.summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Text_Transform>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Cast_Columns>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Output_Parquet>() {
+                        let csv_path1: &String = self.file_dict.get(&input_keys[0]).unwrap();
+                        match node.process_node(csv_path1, node_data, &node_id) {
+                            Ok((summary, parquet_path)) => {
+                                // Kept off the main port, downstream nodes read CSV
+                                self.file_dict.insert(port_key(node_id, "parquet"), parquet_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
+                    } else if let Some(node) = node.downcast_ref::<Db_Source>() {
+                        // Source node, it has no input table
+                        match node.process_node(node_data, &node_id) {
+                            Ok((summary, return_path)) => {
+                                self.file_dict.insert(node_id.to_string(), return_path);
+                                results.push(ProcessedNode { node_id, data: summary });
+                            }
+                            Err(err) => results.push(error_node(node_id, err)),
+                        }
                     }   
                 }
             }
@@ -0,0 +1,226 @@
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use crate::nodes::datetime::{excel_serial_to_datetime, parse_datetime, DATE_OUTPUT_FORMAT, EXCEL_FORMAT, OUTPUT_FORMAT};
+use crate::nodes::table::{is_missing, normalize_localized_number, parse_localized_number, Table};
+
+pub struct Cast_Columns;
+
+// 2^53, below it a float spelling of a whole number parses to exactly that integer
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum TargetType {
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    Datetime,
+    Categorical,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum OnError {
+    Error, // Fail the node on the first unparseable cell
+    Null,  // Empty the cell
+    Keep,  // Leave the original text in place
+}
+
+#[derive(Deserialize)]
+struct Cast {
+    column: String,
+    to: TargetType,
+    #[serde(default)]
+    format: Option<String>, // chrono format for dates, e.g. `%d/%m/%Y`, or `excel` for serial numbers
+    #[serde(default)]
+    output: Option<String>, // Defaults to replacing the column in place
+    #[serde(default)]
+    on_error: Option<OnError>, // Overrides the node-level policy for this column
+    #[serde(default)]
+    decimal_comma: Option<bool>, // Overrides the node-level setting for this column
+}
+
+#[derive(Deserialize)]
+struct CastColumnsConfig {
+    casts: Vec<Cast>,
+    #[serde(default = "default_on_error")]
+    on_error: OnError,
+    #[serde(default)]
+    decimal_comma: bool, // Read "1.234,5" as 1234.5
+}
+
+fn default_on_error() -> OnError {
+    OnError::Null
+}
+
+impl Cast_Columns {
+    // Convert each listed column to a canonical text form of its type, counting cells that fail to parse
+    pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<(serde_json::Value, String), Box<dyn Error>> {
+        let config: CastColumnsConfig = serde_json::from_str(data)?;
+        let mut table = Table::from_path(file1)?;
+
+        let mut summaries: Vec<serde_json::Value> = Vec::new();
+        for cast in &config.casts {
+            let index = table.column_index(&cast.column)?;
+            let on_error = cast.on_error.unwrap_or(config.on_error);
+            let decimal_comma = cast.decimal_comma.unwrap_or(config.decimal_comma);
+
+            let mut failed = 0;
+            let mut missing = 0;
+            let mut failed_examples: Vec<String> = Vec::new();
+            let mut converted: Vec<String> = Vec::with_capacity(table.rows.len());
+            for (position, row) in table.rows.iter().enumerate() {
+                let cell = row.get(index).map(|v| v.as_str()).unwrap_or("");
+                if is_missing(cell) {
+                    missing += 1;
+                    converted.push(String::new());
+                    continue;
+                }
+                match convert(cell, cast, decimal_comma) {
+                    Some(value) => converted.push(value),
+                    None => {
+                        if on_error == OnError::Error {
+                            return Err(format!(
+                                "Cannot cast '{}' in column '{}' (row {}) to {}",
+                                cell,
+                                cast.column,
+                                position + 1,
+                                type_name(cast.to)
+                            )
+                            .into());
+                        }
+                        failed += 1;
+                        if failed_examples.len() < 5 {
+                            failed_examples.push(cell.to_string());
+                        }
+                        converted.push(if on_error == OnError::Keep { cell.to_string() } else { String::new() });
+                    }
+                }
+            }
+
+            let mut summary = json!({
+                "column": cast.column,
+                "to": type_name(cast.to),
+                "converted": table.rows.len() - missing - failed,
+                "missing": missing,
+                "failed": failed,
+                "failed_examples": failed_examples,
+            });
+            if let TargetType::Categorical = cast.to {
+                let levels: BTreeSet<&String> = converted.iter().filter(|v| !v.is_empty()).collect();
+                summary["levels"] = json!(levels);
+            }
+            summaries.push(summary);
+
+            match &cast.output {
+                Some(output) if output != &cast.column => {
+                    let all_rows: Vec<usize> = (0..table.rows.len()).collect();
+                    table.append_columns(&[output.as_str()], &all_rows, &[converted]);
+                }
+                _ => {
+                    for (row, value) in table.rows.iter_mut().zip(converted) {
+                        if let Some(cell) = row.get_mut(index) {
+                            *cell = value;
+                        }
+                    }
+                }
+            }
+        }
+
+        let output_file = format!("./storage_bin/cast_{}.csv", node_id);
+        table.write_to_path(&output_file)?;
+        println!("Cast columns written to {}", output_file);
+
+        Ok((json!({ "columns": summaries, "rows": table.rows.len() }), output_file))
+    }
+}
+
+fn type_name(to: TargetType) -> &'static str {
+    match to {
+        TargetType::Integer => "integer",
+        TargetType::Float => "float",
+        TargetType::Boolean => "boolean",
+        TargetType::Date => "date",
+        TargetType::Datetime => "datetime",
+        TargetType::Categorical => "categorical",
+    }
+}
+
+fn convert(cell: &str, cast: &Cast, decimal_comma: bool) -> Option<String> {
+    let parse_date = || match cast.format.as_deref() {
+        Some(EXCEL_FORMAT) => parse_localized_number(cell, decimal_comma).and_then(excel_serial_to_datetime),
+        format => parse_datetime(cell, format),
+    };
+    match cast.to {
+        // Parsed as i64 so values above 2^53 stay exact. Whole numbers written as floats ("3.0") are
+        // accepted too, as long as the float still holds them exactly; fractional values are not.
+        TargetType::Integer => {
+            let text = normalize_localized_number(cell, decimal_comma)?;
+            match text.parse::<i64>() {
+                Ok(value) => Some(value.to_string()),
+                Err(_) => text
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|v| v.fract() == 0.0 && v.abs() < MAX_EXACT_INTEGER)
+                    .map(|v| (v as i64).to_string()),
+            }
+        }
+        TargetType::Float => parse_localized_number(cell, decimal_comma).map(|v| v.to_string()),
+        TargetType::Boolean => match cell.trim().to_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "1" => Some("true".to_string()),
+            "false" | "f" | "no" | "n" | "0" => Some("false".to_string()),
+            _ => None,
+        },
+        TargetType::Date => parse_date().map(|d| d.format(DATE_OUTPUT_FORMAT).to_string()),
+        TargetType::Datetime => parse_date().map(|d| d.format(OUTPUT_FORMAT).to_string()),
+        TargetType::Categorical => Some(cell.trim().to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::{read_output, storage_bin, write_csv};
+
+    fn cast(to: &str, decimal_comma: bool) -> Cast {
+        serde_json::from_value(json!({ "column": "x", "to": to, "decimal_comma": decimal_comma })).unwrap()
+    }
+
+    #[test]
+    fn integers_above_two_to_the_53_stay_exact() {
+        let integer = cast("integer", false);
+        assert_eq!(convert("9007199254740993", &integer, false).as_deref(), Some("9007199254740993"));
+        assert_eq!(convert(" -9223372036854775808 ", &integer, false).as_deref(), Some("-9223372036854775808"));
+        assert_eq!(convert("9223372036854775808", &integer, false), None);
+        // A float spelling that no longer holds the value exactly is refused rather than rounded
+        assert_eq!(convert("9007199254740993.0", &integer, false), None);
+        assert_eq!(convert("3.0", &integer, false).as_deref(), Some("3"));
+        assert_eq!(convert("3.5", &integer, false), None);
+    }
+
+    #[test]
+    fn localized_integers_and_floats() {
+        assert_eq!(convert("9.007.199.254.740.993", &cast("integer", true), true).as_deref(), Some("9007199254740993"));
+        assert_eq!(convert("12,0", &cast("integer", true), true).as_deref(), Some("12"));
+        assert_eq!(convert("1.234,5", &cast("float", true), true).as_deref(), Some("1234.5"));
+        assert_eq!(convert("1.5", &cast("float", true), true), None);
+    }
+
+    #[test]
+    fn on_error_policies() {
+        storage_bin();
+        let file = write_csv("cast_policies", "x\n1\nabc\nNA\n");
+        let run = |on_error: &str, node_id: u32| {
+            let data = json!({ "casts": [{ "column": "x", "to": "integer" }], "on_error": on_error }).to_string();
+            Cast_Columns.process_node(&file, &data, &node_id).map(|(_, path)| path)
+        };
+        let column = |rows: Vec<Vec<String>>| rows[1..].iter().map(|r| r[0].clone()).collect::<Vec<_>>();
+        assert_eq!(column(read_output(run("null", 900_070))), vec!["1", "", ""]);
+        assert_eq!(column(read_output(run("keep", 900_071))), vec!["1", "abc", ""]);
+        assert!(run("error", 900_072).unwrap_err().to_string().contains("row 2"));
+    }
+}
@@ -0,0 +1,372 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::{Component, Path};
+use std::sync::Mutex;
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::nodes::json_import::JsonImportOptions;
+use crate::nodes::spreadsheet::SpreadsheetOptions;
+
+// Errors cross awaits in the spawned socket task, so they must be Send
+pub type UploadError = Box<dyn Error + Send + Sync>;
+
+const DEFAULT_CHUNK_SIZE: u64 = 1024 * 1024;
+const MIN_CHUNK_SIZE: u64 = 64 * 1024;
+const MAX_CHUNK_SIZE: u64 = 16 * 1024 * 1024;
+const CHUNK_INDEX_BYTES: usize = 4;
+// Largest file a client may declare, the same budget an archive may expand to (see compressed.rs)
+const MAX_UPLOAD_BYTES: u64 = 8 * 1024 * 1024 * 1024;
+
+// Files with an upload in progress, a second connection must not append to the same partial file
+static ACTIVE_UPLOADS: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// Holds a file in `ACTIVE_UPLOADS` until the upload is committed, aborted or dropped
+struct ActiveUpload(String);
+
+impl ActiveUpload {
+    fn claim(file_path: &str) -> Result<ActiveUpload, UploadError> {
+        let mut active = ACTIVE_UPLOADS.lock().unwrap_or_else(|e| e.into_inner());
+        if !active.insert(file_path.to_string()) {
+            return Err(format!("An upload of '{}' is already in progress", file_path).into());
+        }
+        Ok(ActiveUpload(file_path.to_string()))
+    }
+}
+
+impl Drop for ActiveUpload {
+    fn drop(&mut self) {
+        ACTIVE_UPLOADS.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.0);
+    }
+}
+
+// Text messages of the chunked upload protocol, told apart by `"upload"`. After `begin` the client
+// sends binary chunks, each a big-endian u32 chunk number followed by the bytes, then `commit`.
+#[derive(Deserialize)]
+#[serde(tag = "upload", rename_all = "lowercase")]
+pub enum UploadMessage {
+    Begin(Box<BeginUpload>),
+    Commit,
+    Abort,
+}
+
+#[derive(Deserialize)]
+pub struct BeginUpload {
+    pub id: String,
+    pub file_name: String,
+    pub size: u64,
+    pub sha256: String, // Hex digest of the whole file
+    #[serde(default)]
+    pub chunk_size: Option<u64>, // 1 MiB when not set
+    #[serde(default)]
+    pub spreadsheet: Option<SpreadsheetOptions>,
+    #[serde(default)]
+    pub json: Option<JsonImportOptions>,
+}
+
+// Stored next to the partial file; a `begin` with the same values resumes instead of starting over
+#[derive(Serialize, Deserialize, PartialEq)]
+struct UploadState {
+    size: u64,
+    sha256: String,
+    chunk_size: u64,
+}
+
+// An upload in progress. Chunks must arrive in order, so the length of the partial file is all
+// the progress there is to remember between connections.
+pub struct ChunkedUpload {
+    pub id: String, // The id the client chose, nodes find the imported file under it
+    pub upload_id: String,
+    pub file_path: String,
+    part_path: String,
+    state_path: String,
+    state: UploadState,
+    received: u64,
+    part: File,
+    _active: ActiveUpload,
+}
+
+// A single normal path component, no separators, `.` or `..`
+pub fn is_path_component(value: &str) -> bool {
+    let mut components = Path::new(value).components();
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+impl ChunkedUpload {
+    // The file goes to `<root>/<id>/<file name>`
+    pub async fn begin(root: &str, begin: &BeginUpload) -> Result<ChunkedUpload, UploadError> {
+        // The id must be one plain path component and only the last component of the file name is
+        // used, so a client cannot write outside `root`
+        if !is_path_component(&begin.id) {
+            return Err(format!("Invalid upload id '{}'", begin.id).into());
+        }
+        let file_name = Path::new(&begin.file_name)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .filter(|n| !n.is_empty())
+            .ok_or_else(|| format!("Invalid file name '{}'", begin.file_name))?;
+        let sha256 = begin.sha256.to_lowercase();
+        if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err("sha256 must be a 64 character hex digest".into());
+        }
+        if begin.size > MAX_UPLOAD_BYTES {
+            return Err(format!("Uploads are limited to {} bytes, got {}", MAX_UPLOAD_BYTES, begin.size).into());
+        }
+        let chunk_size = begin.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+        if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+            return Err(format!("chunk_size must be between {} and {} bytes", MIN_CHUNK_SIZE, MAX_CHUNK_SIZE).into());
+        }
+
+        let directory = format!("{}/{}", root.trim_end_matches('/'), begin.id);
+        fs::create_dir_all(&directory).await?;
+        let file_path = format!("{}/{}", directory, file_name);
+        let part_path = format!("{}.part", file_path);
+        let state_path = format!("{}.upload.json", file_path);
+        let active = ActiveUpload::claim(&file_path)?;
+        let state = UploadState { size: begin.size, sha256, chunk_size };
+
+        let stored: Option<UploadState> = fs::read_to_string(&state_path).await.ok().and_then(|text| serde_json::from_str(&text).ok());
+        let existing = fs::metadata(&part_path).await.map(|m| m.len()).ok();
+        let received = match (stored, existing) {
+            // Drop a chunk that was only partly written when the connection went away
+            (Some(stored), Some(length)) if stored == state => (length / chunk_size * chunk_size).min(state.size),
+            _ => 0,
+        };
+        let part = OpenOptions::new().create(true).write(true).truncate(false).open(&part_path).await?;
+        part.set_len(received).await?;
+        fs::write(&state_path, serde_json::to_string(&state)?).await?;
+        let part = OpenOptions::new().append(true).open(&part_path).await?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(file_path.as_bytes());
+        hasher.update(state.size.to_be_bytes());
+        hasher.update(state.sha256.as_bytes());
+        let upload_id = format!("{:x}", hasher.finalize())[..16].to_string();
+
+        Ok(ChunkedUpload { id: begin.id.clone(), upload_id, file_path, part_path, state_path, state, received, part, _active: active })
+    }
+
+    pub fn next_chunk(&self) -> u64 {
+        self.received.div_ceil(self.state.chunk_size)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.received == self.state.size
+    }
+
+    pub fn progress(&self) -> serde_json::Value {
+        json!({
+            "upload": "progress",
+            "upload_id": self.upload_id,
+            "next_chunk": self.next_chunk(),
+            "chunk_size": self.state.chunk_size,
+            "received": self.received,
+            "size": self.state.size,
+        })
+    }
+
+    // Append the next chunk; a chunk that was already written (resent after a reconnect) is ignored
+    pub async fn write_chunk(&mut self, message: &[u8]) -> Result<(), UploadError> {
+        if message.len() < CHUNK_INDEX_BYTES {
+            return Err("Chunk is missing its number".into());
+        }
+        let (index, data) = message.split_at(CHUNK_INDEX_BYTES);
+        let index = u32::from_be_bytes([index[0], index[1], index[2], index[3]]) as u64;
+        let expected = self.next_chunk();
+        if index < expected {
+            return Ok(());
+        }
+        if index > expected {
+            return Err(format!("Expected chunk {}, got {}", expected, index).into());
+        }
+        let length = self.state.chunk_size.min(self.state.size - self.received);
+        if data.len() as u64 != length {
+            return Err(format!("Chunk {} should hold {} bytes, got {}", index, length, data.len()).into());
+        }
+        self.part.write_all(data).await?;
+        self.part.flush().await?;
+        self.received += length;
+        Ok(())
+    }
+
+    // Check the digest and move the file into place; a mismatch discards the upload
+    pub async fn commit(self) -> Result<String, UploadError> {
+        if !self.is_complete() {
+            return Err(format!("Upload incomplete, next chunk is {}", self.next_chunk()).into());
+        }
+        self.part.sync_all().await?;
+
+        let mut hasher = Sha256::new();
+        let mut part = File::open(&self.part_path).await?;
+        let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE as usize];
+        loop {
+            let read = part.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        let digest = format!("{:x}", hasher.finalize());
+        if digest != self.state.sha256 {
+            self.abort().await;
+            return Err(format!("Checksum mismatch, got {}; the upload was discarded", digest).into());
+        }
+
+        fs::rename(&self.part_path, &self.file_path).await?;
+        let _ = fs::remove_file(&self.state_path).await;
+        println!("✅ File saved at {}", self.file_path);
+        Ok(self.file_path)
+    }
+
+    pub async fn abort(self) {
+        let _ = fs::remove_file(&self.part_path).await;
+        let _ = fs::remove_file(&self.state_path).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNK: usize = MIN_CHUNK_SIZE as usize;
+
+    fn root(name: &str) -> String {
+        let directory = std::env::temp_dir().join(format!("istat_tests_{}", std::process::id())).join(name);
+        let _ = std::fs::remove_dir_all(&directory);
+        directory.to_string_lossy().into_owned()
+    }
+
+    // Two full chunks and a short last one
+    fn contents() -> Vec<u8> {
+        (0..2 * CHUNK + 1000).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn begin_message(id: &str, file_name: &str, contents: &[u8]) -> BeginUpload {
+        BeginUpload {
+            id: id.to_string(),
+            file_name: file_name.to_string(),
+            size: contents.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(contents)),
+            chunk_size: Some(MIN_CHUNK_SIZE),
+            spreadsheet: None,
+            json: None,
+        }
+    }
+
+    fn chunk(contents: &[u8], index: usize) -> Vec<u8> {
+        let mut message = (index as u32).to_be_bytes().to_vec();
+        message.extend_from_slice(&contents[index * CHUNK..((index + 1) * CHUNK).min(contents.len())]);
+        message
+    }
+
+    #[tokio::test]
+    async fn ids_and_file_names_cannot_leave_the_root() {
+        let root = root("chunked_paths");
+        let contents = contents();
+        for id in ["..", "../other", "a/b", "", ".", "/tmp"] {
+            let error = ChunkedUpload::begin(&root, &begin_message(id, "data.csv", &contents)).await.err().unwrap();
+            assert!(error.to_string().contains("Invalid upload id"), "{}", id);
+        }
+        let upload = ChunkedUpload::begin(&root, &begin_message("42", "../../data.csv", &contents)).await.unwrap();
+        assert_eq!(upload.file_path, format!("{}/42/data.csv", root));
+        assert!(!is_path_component("..") && is_path_component("data.csv"));
+    }
+
+    #[tokio::test]
+    async fn resumes_after_a_reconnect_and_ignores_duplicate_chunks() {
+        let root = root("chunked_resume");
+        let contents = contents();
+        let begin = begin_message("7", "data.bin", &contents);
+
+        let mut upload = ChunkedUpload::begin(&root, &begin).await.unwrap();
+        upload.write_chunk(&chunk(&contents, 0)).await.unwrap();
+        upload.write_chunk(&chunk(&contents, 0)).await.unwrap();
+        assert_eq!(upload.next_chunk(), 1);
+        // The connection drops halfway through the next chunk
+        upload.part.write_all(&contents[CHUNK..CHUNK + 10]).await.unwrap();
+        upload.part.flush().await.unwrap();
+        drop(upload);
+
+        let mut upload = ChunkedUpload::begin(&root, &begin).await.unwrap();
+        assert_eq!((upload.next_chunk(), upload.progress()["received"].as_u64()), (1, Some(CHUNK as u64)));
+        let error = upload.write_chunk(&chunk(&contents, 2)).await.err().unwrap();
+        assert_eq!(error.to_string(), "Expected chunk 1, got 2");
+        assert!(!upload.is_complete());
+        upload.write_chunk(&chunk(&contents, 1)).await.unwrap();
+        upload.write_chunk(&chunk(&contents, 2)).await.unwrap();
+
+        let file_path = upload.commit().await.unwrap();
+        assert_eq!(std::fs::read(&file_path).unwrap(), contents);
+        assert!(!Path::new(&format!("{}.part", file_path)).exists());
+        assert!(!Path::new(&format!("{}.upload.json", file_path)).exists());
+    }
+
+    #[tokio::test]
+    async fn a_different_file_starts_over() {
+        let root = root("chunked_restart");
+        let contents = contents();
+        let mut upload = ChunkedUpload::begin(&root, &begin_message("7", "data.bin", &contents)).await.unwrap();
+        upload.write_chunk(&chunk(&contents, 0)).await.unwrap();
+        drop(upload);
+
+        let other: Vec<u8> = contents.iter().rev().copied().collect();
+        let upload = ChunkedUpload::begin(&root, &begin_message("7", "data.bin", &other)).await.unwrap();
+        assert_eq!(upload.next_chunk(), 0);
+    }
+
+    #[tokio::test]
+    async fn rejects_short_chunks_and_discards_a_checksum_mismatch() {
+        let root = root("chunked_checksum");
+        let contents = contents();
+        let mut begin = begin_message("9", "data.bin", &contents);
+        begin.sha256 = "0".repeat(64);
+        let mut upload = ChunkedUpload::begin(&root, &begin).await.unwrap();
+
+        let mut short = chunk(&contents, 0);
+        short.pop();
+        assert!(upload.write_chunk(&short).await.unwrap_err().to_string().contains("should hold"));
+        assert!(upload.write_chunk(&[0, 0]).await.is_err());
+        for index in 0..3 {
+            upload.write_chunk(&chunk(&contents, index)).await.unwrap();
+        }
+        let file_path = upload.file_path.clone();
+        assert!(upload.commit().await.unwrap_err().to_string().starts_with("Checksum mismatch"));
+        assert!(!Path::new(&file_path).exists());
+        assert!(!Path::new(&format!("{}.part", file_path)).exists());
+    }
+
+    #[tokio::test]
+    async fn validates_the_digest_and_chunk_size() {
+        let root = root("chunked_settings");
+        let contents = contents();
+        let mut begin = begin_message("1", "data.bin", &contents);
+        begin.sha256 = "abc".to_string();
+        assert!(ChunkedUpload::begin(&root, &begin).await.is_err());
+        let mut begin = begin_message("1", "data.bin", &contents);
+        begin.chunk_size = Some(MAX_CHUNK_SIZE + 1);
+        assert!(ChunkedUpload::begin(&root, &begin).await.is_err());
+        let mut begin = begin_message("1", "data.bin", &contents);
+        begin.size = MAX_UPLOAD_BYTES + 1;
+        assert!(ChunkedUpload::begin(&root, &begin).await.err().unwrap().to_string().starts_with("Uploads are limited"));
+    }
+
+    #[tokio::test]
+    async fn one_upload_of_a_file_at_a_time() {
+        let root = root("chunked_exclusive");
+        let contents = contents();
+        let begin = begin_message("3", "data.bin", &contents);
+        let upload = ChunkedUpload::begin(&root, &begin).await.unwrap();
+        let error = ChunkedUpload::begin(&root, &begin).await.err().unwrap();
+        assert!(error.to_string().contains("already in progress"));
+        assert!(ChunkedUpload::begin(&root, &begin_message("3", "other.bin", &contents)).await.is_ok());
+
+        upload.abort().await;
+        let upload = ChunkedUpload::begin(&root, &begin).await.unwrap();
+        drop(upload);
+        assert!(ChunkedUpload::begin(&root, &begin).await.is_ok());
+    }
+}
@@ -1,11 +1,13 @@
-use csv::{ReaderBuilder, WriterBuilder, StringRecord};
+use csv::{WriterBuilder, StringRecord};
 use std::error::Error;
 use std::fs;
+
+use crate::nodes::dialect::open_reader;
 pub struct Clean_By_Column;
 
 impl Clean_By_Column {
     pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<String, Box<dyn Error>> {
-        let mut reader1 = ReaderBuilder::new().from_path(file1)?;
+        let mut reader1 = open_reader(file1)?;
     
         // Get the headers and find the index of the `data` column
         let headers = reader1.headers()?.clone();
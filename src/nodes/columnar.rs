@@ -0,0 +1,156 @@
+use arrow::datatypes::SchemaRef;
+use arrow::error::ArrowError;
+use arrow::ipc::reader::{FileReader, StreamReader};
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use csv::WriterBuilder;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde_json::json;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::nodes::datetime::{DATE_OUTPUT_FORMAT, OUTPUT_FORMAT};
+use crate::nodes::dialect::{self, Dialect};
+use crate::nodes::export::write_atomically;
+
+const PARQUET_EXTENSIONS: [&str; 1] = ["parquet"];
+const IPC_EXTENSIONS: [&str; 4] = ["arrow", "arrows", "feather", "ipc"];
+
+fn extension(file_path: &str) -> String {
+    Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default()
+}
+
+pub fn is_columnar(file_path: &str) -> bool {
+    let extension = extension(file_path);
+    PARQUET_EXTENSIONS.contains(&extension.as_str()) || IPC_EXTENSIONS.contains(&extension.as_str())
+}
+
+type Batches = Box<dyn Iterator<Item = Result<RecordBatch, ArrowError>>>;
+
+fn open_batches(file_path: &str) -> Result<(SchemaRef, Batches), Box<dyn Error>> {
+    if PARQUET_EXTENSIONS.contains(&extension(file_path).as_str()) {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(file_path)?)?;
+        let schema = builder.schema().clone();
+        return Ok((schema, Box::new(builder.build()?)));
+    }
+    // IPC files have a footer, streams do not; try the file format first
+    match FileReader::try_new(BufReader::new(File::open(file_path)?), None) {
+        Ok(reader) => Ok((reader.schema(), Box::new(reader))),
+        Err(_) => {
+            let reader = StreamReader::try_new(BufReader::new(File::open(file_path)?), None)?;
+            Ok((reader.schema(), Box::new(reader)))
+        }
+    }
+}
+
+// Stream a Parquet or Arrow IPC file into CSV batch by batch, nulls become empty cells
+pub fn convert_to_csv(file_path: &str) -> Result<(String, serde_json::Value), Box<dyn Error>> {
+    let (schema, batches) = open_batches(file_path)?;
+    let output_file = format!("{}.csv", file_path);
+    // Downloads and previews convert on every request, never truncate a table another request is reading
+    let rows = write_atomically(&output_file, |path| {
+        let mut writer = WriterBuilder::new().from_path(path)?;
+        writer.write_record(schema.fields().iter().map(|f| f.name().as_str()))?;
+
+        let options = FormatOptions::default()
+            .with_null("")
+            .with_date_format(Some(DATE_OUTPUT_FORMAT))
+            .with_datetime_format(Some(OUTPUT_FORMAT))
+            .with_timestamp_format(Some(OUTPUT_FORMAT));
+        let mut rows = 0;
+        for batch in batches {
+            let batch = batch?;
+            let formatters = batch
+                .columns()
+                .iter()
+                .map(|column| ArrayFormatter::try_new(column.as_ref(), &options))
+                .collect::<Result<Vec<_>, _>>()?;
+            for row in 0..batch.num_rows() {
+                writer.write_record(formatters.iter().map(|f| f.value(row).to_string()))?;
+            }
+            rows += batch.num_rows();
+        }
+        writer.flush()?;
+        Ok(rows)
+    })?;
+    dialect::save(&output_file, &Dialect::default())?;
+    println!("{} written to {}", file_path, output_file);
+
+    let columns: Vec<serde_json::Value> = schema
+        .fields()
+        .iter()
+        .map(|f| json!({ "name": f.name(), "type": f.data_type().to_string() }))
+        .collect();
+    Ok((
+        output_file,
+        json!({
+            "format": if PARQUET_EXTENSIONS.contains(&extension(file_path).as_str()) { "parquet" } else { "arrow-ipc" },
+            "rows": rows,
+            "columns": columns,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::ipc::writer::{FileWriter, StreamWriter};
+    use std::sync::Arc;
+
+    fn batch() -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true), Field::new("name", DataType::Utf8, true)]));
+        RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int64Array::from(vec![Some(1), None])), Arc::new(StringArray::from(vec![Some("a, b"), Some("c")]))],
+        )
+        .unwrap()
+    }
+
+    fn scratch(name: &str) -> String {
+        let directory = std::env::temp_dir().join(format!("istat_tests_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory.join(name).to_string_lossy().into_owned()
+    }
+
+    fn converted_rows(path: &str) -> Vec<Vec<String>> {
+        let (csv_path, info) = convert_to_csv(path).unwrap();
+        assert_eq!(info["format"], "arrow-ipc");
+        assert_eq!(info["columns"][0]["type"], "Int64");
+        let mut reader = csv::Reader::from_path(csv_path).unwrap();
+        reader.records().map(|r| r.unwrap().iter().map(|v| v.to_string()).collect()).collect()
+    }
+
+    #[test]
+    fn reads_ipc_files_and_streams() {
+        let batch = batch();
+        let file_path = scratch("columnar_file.arrow");
+        let mut writer = FileWriter::try_new(File::create(&file_path).unwrap(), &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+
+        let stream_path = scratch("columnar_stream.arrows");
+        let mut writer = StreamWriter::try_new(File::create(&stream_path).unwrap(), &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+
+        let expected = vec![vec!["1".to_string(), "a, b".to_string()], vec![String::new(), "c".to_string()]];
+        assert_eq!(converted_rows(&file_path), expected);
+        assert_eq!(converted_rows(&stream_path), expected);
+    }
+
+    #[test]
+    fn recognises_columnar_extensions() {
+        assert!(is_columnar("data.Parquet"));
+        assert!(is_columnar("data.feather"));
+        assert!(!is_columnar("data.csv"));
+        assert!(!is_columnar("parquet"));
+    }
+}
@@ -0,0 +1,195 @@
+use flate2::read::MultiGzDecoder;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use zip::ZipArchive;
+
+use crate::nodes::columnar;
+use crate::nodes::json_import;
+use crate::nodes::spreadsheet;
+
+const GZIP_EXTENSIONS: [&str; 2] = ["gz", "gzip"];
+const ZSTD_EXTENSIONS: [&str; 2] = ["zst", "zstd"];
+const ZIP_EXTENSIONS: [&str; 1] = ["zip"];
+const DELIMITED_EXTENSIONS: [&str; 4] = ["csv", "tsv", "tab", "txt"];
+
+// Guard against archives that expand far beyond their size, counted over all files of one upload
+const MAX_EXPANDED_BYTES: u64 = 8 * 1024 * 1024 * 1024;
+
+fn extension(file_path: &str) -> String {
+    Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default()
+}
+
+pub fn is_compressed(file_path: &str) -> bool {
+    let extension = extension(file_path);
+    [&GZIP_EXTENSIONS[..], &ZSTD_EXTENSIONS[..], &ZIP_EXTENSIONS[..]]
+        .iter()
+        .any(|extensions| extensions.contains(&extension.as_str()))
+}
+
+// Archives hold several files, gzip and zstd wrap a single one
+pub fn is_archive(file_path: &str) -> bool {
+    ZIP_EXTENSIONS.contains(&extension(file_path).as_str())
+}
+
+// Files inside an archive that can become a table, anything else (readmes, images) is left out
+fn is_table(file_path: &str) -> bool {
+    DELIMITED_EXTENSIONS.contains(&extension(file_path).as_str())
+        || spreadsheet::is_spreadsheet(file_path)
+        || columnar::is_columnar(file_path)
+        || json_import::is_json(file_path)
+}
+
+// Expand a compressed upload next to it, streaming from disk to disk. Returns the name of each
+// expanded file (its path inside a zip archive) and where it was written.
+pub fn decompress(file_path: &str) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    if is_archive(file_path) {
+        return extract_zip(file_path);
+    }
+    let extension = extension(file_path);
+
+    // `sales.csv.gz` becomes `sales.csv`
+    let output_file = file_path[..file_path.len() - extension.len() - 1].to_string();
+    let source = BufReader::new(File::open(file_path)?);
+    let decoder: Box<dyn Read> = if GZIP_EXTENSIONS.contains(&extension.as_str()) {
+        Box::new(MultiGzDecoder::new(source))
+    } else {
+        Box::new(zstd::stream::read::Decoder::with_buffer(source)?)
+    };
+    let mut budget = MAX_EXPANDED_BYTES;
+    copy_limited(decoder, &output_file, &mut budget)?;
+    println!("{} decompressed to {}", file_path, output_file);
+
+    let name = Path::new(&output_file)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| output_file.clone());
+    Ok(vec![(name, output_file)])
+}
+
+// Members go to a directory named after the archive, `data.zip` extracts into `data/`
+fn extract_zip(file_path: &str) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let mut archive = ZipArchive::new(BufReader::new(File::open(file_path)?))?;
+    let directory = Path::new(file_path).with_extension("");
+    let mut budget = MAX_EXPANDED_BYTES;
+    let mut extracted: Vec<(String, String)> = Vec::new();
+
+    for index in 0..archive.len() {
+        let member = archive.by_index(index)?;
+        // `enclosed_name` rejects absolute paths and `..`, so nothing lands outside the directory
+        let Some(name) = member.enclosed_name() else {
+            continue;
+        };
+        let hidden = name.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.') || c.as_os_str() == "__MACOSX");
+        let name = name.to_string_lossy().replace('\\', "/");
+        if member.is_dir() || hidden || !is_table(&name) {
+            continue;
+        }
+
+        let output_file = directory.join(&name);
+        if let Some(parent) = output_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let output_file = output_file.to_string_lossy().into_owned();
+        copy_limited(member, &output_file, &mut budget)?;
+        extracted.push((name, output_file));
+    }
+
+    if extracted.is_empty() {
+        return Err(format!("{} contains no CSV, spreadsheet, Parquet or JSON files", file_path).into());
+    }
+    println!("{} files of {} extracted to {}", extracted.len(), file_path, directory.display());
+    Ok(extracted)
+}
+
+fn copy_limited(reader: impl Read, output_file: &str, budget: &mut u64) -> Result<(), Box<dyn Error>> {
+    let mut output = File::create(output_file)?;
+    let written = io::copy(&mut reader.take(*budget + 1), &mut output)?;
+    if written > *budget {
+        drop(output);
+        std::fs::remove_file(output_file)?;
+        return Err(format!("Upload expands to more than {} bytes", MAX_EXPANDED_BYTES).into());
+    }
+    *budget -= written;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    // A fresh directory per test, extracted members land next to the archive
+    fn scratch(name: &str) -> std::path::PathBuf {
+        let directory = std::env::temp_dir().join(format!("istat_tests_{}", std::process::id())).join(name);
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn gzip_and_zstd_expand_next_to_the_upload() {
+        let directory = scratch("compressed_single");
+        let gzip = directory.join("sales.csv.gz").to_string_lossy().into_owned();
+        let mut encoder = GzEncoder::new(File::create(&gzip).unwrap(), Compression::default());
+        encoder.write_all(b"a,b\n1,2\n").unwrap();
+        encoder.finish().unwrap();
+        let zstd_path = directory.join("costs.tsv.zst").to_string_lossy().into_owned();
+        std::fs::write(&zstd_path, zstd::encode_all(&b"a\tb\n3\t4\n"[..], 0).unwrap()).unwrap();
+
+        let expanded = decompress(&gzip).unwrap();
+        assert_eq!(expanded, vec![("sales.csv".to_string(), gzip.trim_end_matches(".gz").to_string())]);
+        assert_eq!(std::fs::read_to_string(&expanded[0].1).unwrap(), "a,b\n1,2\n");
+        let expanded = decompress(&zstd_path).unwrap();
+        assert_eq!(std::fs::read_to_string(&expanded[0].1).unwrap(), "a\tb\n3\t4\n");
+        assert!(is_compressed(&gzip) && !is_archive(&gzip));
+    }
+
+    #[test]
+    fn zip_members_cannot_escape_the_archive_directory() {
+        let directory = scratch("compressed_zip");
+        let archive = directory.join("data.zip").to_string_lossy().into_owned();
+        let mut writer = ZipWriter::new(File::create(&archive).unwrap());
+        for name in ["sales/2024.csv", "../escaped.csv", "/absolute.csv", "__MACOSX/sales/._2024.csv", ".hidden.csv", "readme.md"] {
+            writer.start_file(name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(b"x\n1\n").unwrap();
+        }
+        writer.finish().unwrap();
+
+        let extracted = decompress(&archive).unwrap();
+        let expected_path = directory.join("data").join("sales/2024.csv").to_string_lossy().into_owned();
+        assert_eq!(extracted, vec![("sales/2024.csv".to_string(), expected_path)]);
+        assert!(!directory.join("escaped.csv").exists());
+    }
+
+    #[test]
+    fn archives_without_tables_are_rejected() {
+        let directory = scratch("compressed_empty");
+        let archive = directory.join("docs.zip").to_string_lossy().into_owned();
+        let mut writer = ZipWriter::new(File::create(&archive).unwrap());
+        writer.start_file("readme.md", SimpleFileOptions::default()).unwrap();
+        writer.finish().unwrap();
+        assert!(decompress(&archive).unwrap_err().to_string().contains("contains no CSV"));
+    }
+
+    #[test]
+    fn expansion_beyond_the_budget_is_discarded() {
+        let directory = scratch("compressed_budget");
+        let output_file = directory.join("big.csv").to_string_lossy().into_owned();
+        let mut budget = 4;
+        assert!(copy_limited(&b"0123456789"[..], &output_file, &mut budget).is_err());
+        assert!(!Path::new(&output_file).exists());
+        let mut budget = 10;
+        copy_limited(&b"0123456789"[..], &output_file, &mut budget).unwrap();
+        assert_eq!(budget, 0);
+    }
+}
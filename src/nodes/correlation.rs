@@ -0,0 +1,306 @@
+use csv::WriterBuilder;
+use serde::Deserialize;
+use serde_json::json;
+use statrs::distribution::{ContinuousCDF, Normal, StudentsT};
+use std::error::Error;
+
+use crate::nodes::descriptive::{average_ranks, mean};
+use crate::nodes::table::Table;
+
+pub struct Correlation_Matrix;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum CorrelationMethod {
+    Pearson,
+    Spearman,
+    Kendall,
+}
+
+#[derive(Deserialize)]
+struct CorrelationConfig {
+    columns: Vec<String>,
+    #[serde(default = "default_method")]
+    method: CorrelationMethod,
+}
+
+fn default_method() -> CorrelationMethod {
+    CorrelationMethod::Pearson
+}
+
+struct PairResult {
+    coefficient: f64,
+    p_value: f64,
+    n: usize,
+}
+
+impl Correlation_Matrix {
+    // Pairwise correlations using the rows where both columns are present.
+    // Writes the long table (one row per pair) for downstream nodes and returns it with the matrix form.
+    pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<(serde_json::Value, String), Box<dyn Error>> {
+        let config: CorrelationConfig = serde_json::from_str(data)?;
+        if config.columns.len() < 2 {
+            return Err("At least two columns are required for a correlation matrix".into());
+        }
+
+        let table = Table::from_path(file1)?;
+        let mut columns: Vec<Vec<Option<f64>>> = Vec::new();
+        for column in &config.columns {
+            columns.push(table.numeric_column(table.column_index(column)?));
+        }
+
+        let k = columns.len();
+        let mut coefficients = vec![vec![1.0; k]; k];
+        let mut p_values = vec![vec![0.0; k]; k];
+        let mut counts = vec![vec![0usize; k]; k];
+        let mut long: Vec<serde_json::Value> = Vec::new();
+
+        let output_file = format!("./storage_bin/correlation_{}.csv", node_id);
+        let mut writer = WriterBuilder::new().from_path(&output_file)?;
+        writer.write_record(["column_x", "column_y", "coefficient", "p_value", "n"])?;
+
+        for i in 0..k {
+            counts[i][i] = columns[i].iter().filter(|v| v.is_some()).count();
+            for j in (i + 1)..k {
+                let (x, y): (Vec<f64>, Vec<f64>) = columns[i]
+                    .iter()
+                    .zip(columns[j].iter())
+                    .filter_map(|(a, b)| Some(((*a)?, (*b)?)))
+                    .unzip();
+                let result = correlate(&x, &y, config.method)?;
+
+                coefficients[i][j] = result.coefficient;
+                coefficients[j][i] = result.coefficient;
+                p_values[i][j] = result.p_value;
+                p_values[j][i] = result.p_value;
+                counts[i][j] = result.n;
+                counts[j][i] = result.n;
+
+                writer.write_record([
+                    config.columns[i].clone(),
+                    config.columns[j].clone(),
+                    result.coefficient.to_string(),
+                    result.p_value.to_string(),
+                    result.n.to_string(),
+                ])?;
+                long.push(json!({
+                    "column_x": config.columns[i],
+                    "column_y": config.columns[j],
+                    "coefficient": result.coefficient,
+                    "p_value": result.p_value,
+                    "n": result.n,
+                }));
+            }
+        }
+        writer.flush()?;
+        println!("Correlation table written to {}", output_file);
+
+        let method = match config.method {
+            CorrelationMethod::Pearson => "pearson",
+            CorrelationMethod::Spearman => "spearman",
+            CorrelationMethod::Kendall => "kendall",
+        };
+
+        Ok((
+            json!({
+                "method": method,
+                "long": long,
+                "matrix": {
+                    "columns": config.columns,
+                    "coefficients": coefficients,
+                    "p_values": p_values,
+                    "n": counts,
+                },
+            }),
+            output_file,
+        ))
+    }
+}
+
+fn correlate(x: &[f64], y: &[f64], method: CorrelationMethod) -> Result<PairResult, Box<dyn Error>> {
+    let n = x.len();
+    if n < 3 {
+        return Ok(PairResult { coefficient: f64::NAN, p_value: f64::NAN, n });
+    }
+
+    match method {
+        CorrelationMethod::Pearson | CorrelationMethod::Spearman => {
+            let coefficient = if method == CorrelationMethod::Pearson {
+                pearson(x, y)
+            } else {
+                pearson(&average_ranks(x), &average_ranks(y))
+            };
+            // t test with n - 2 degrees of freedom, the usual large sample approximation for Spearman
+            let df = (n - 2) as f64;
+            let t = coefficient * (df / (1.0 - coefficient * coefficient)).sqrt();
+            let p_value = 2.0 * (1.0 - StudentsT::new(0.0, 1.0, df)?.cdf(t.abs()));
+            Ok(PairResult { coefficient, p_value, n })
+        }
+        CorrelationMethod::Kendall => {
+            let (coefficient, z) = kendall_tau_b(x, y);
+            let p_value = 2.0 * (1.0 - Normal::new(0.0, 1.0)?.cdf(z.abs()));
+            Ok(PairResult { coefficient, p_value, n })
+        }
+    }
+}
+
+fn pearson(x: &[f64], y: &[f64]) -> f64 {
+    let mean_x = mean(x);
+    let mean_y = mean(y);
+    let mut covariance = 0.0;
+    let mut variance_x = 0.0;
+    let mut variance_y = 0.0;
+    for (a, b) in x.iter().zip(y.iter()) {
+        covariance += (a - mean_x) * (b - mean_y);
+        variance_x += (a - mean_x).powi(2);
+        variance_y += (b - mean_y).powi(2);
+    }
+    covariance / (variance_x * variance_y).sqrt()
+}
+
+// Tau-b, which corrects for ties in either column, with the z score of its tie-corrected variance.
+// Knight's algorithm: sort by x, then count the swaps a merge sort by y needs, O(n log n).
+fn kendall_tau_b(x: &[f64], y: &[f64]) -> (f64, f64) {
+    let n = x.len();
+    let mut pairs: Vec<(f64, f64)> = x.iter().copied().zip(y.iter().copied()).collect();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+
+    let x_ties = tie_groups(&pairs, |a, b| a.0 == b.0);
+    let joint_ties = tie_groups(&pairs, |a, b| a.0 == b.0 && a.1 == b.1);
+    let mut ys: Vec<f64> = pairs.iter().map(|p| p.1).collect();
+    let mut buffer = ys.clone();
+    let swaps = merge_count(&mut ys, &mut buffer) as f64;
+    let y_pairs: Vec<(f64, f64)> = ys.iter().map(|v| (*v, 0.0)).collect();
+    let y_ties = tie_groups(&y_pairs, |a, b| a.0 == b.0);
+
+    let pairs_of = |groups: &[f64]| groups.iter().map(|t| t * (t - 1.0) / 2.0).sum::<f64>();
+    let nf = n as f64;
+    let n0 = nf * (nf - 1.0) / 2.0;
+    let (n1, n2, n3) = (pairs_of(&x_ties), pairs_of(&y_ties), pairs_of(&joint_ties));
+    let s = n0 - n1 - n2 + n3 - 2.0 * swaps;
+    let tau = s / ((n0 - n1) * (n0 - n2)).sqrt();
+
+    // Variance of S under independence with ties in both columns (Kendall, 1970)
+    let sum = |groups: &[f64], f: &dyn Fn(f64) -> f64| groups.iter().map(|t| f(*t)).sum::<f64>();
+    let v0 = nf * (nf - 1.0) * (2.0 * nf + 5.0);
+    let vt = sum(&x_ties, &|t| t * (t - 1.0) * (2.0 * t + 5.0));
+    let vu = sum(&y_ties, &|u| u * (u - 1.0) * (2.0 * u + 5.0));
+    let v1 = sum(&x_ties, &|t| t * (t - 1.0)) * sum(&y_ties, &|u| u * (u - 1.0));
+    let v2 = sum(&x_ties, &|t| t * (t - 1.0) * (t - 2.0)) * sum(&y_ties, &|u| u * (u - 1.0) * (u - 2.0));
+    let variance = (v0 - vt - vu) / 18.0 + v1 / (2.0 * nf * (nf - 1.0)) + v2 / (9.0 * nf * (nf - 1.0) * (nf - 2.0));
+    (tau, s / variance.sqrt())
+}
+
+// Sizes of the runs of equal neighbours in sorted data
+fn tie_groups(sorted: &[(f64, f64)], equal: impl Fn(&(f64, f64), &(f64, f64)) -> bool) -> Vec<f64> {
+    let mut groups: Vec<f64> = Vec::new();
+    let mut run = 1.0;
+    for window in sorted.windows(2) {
+        if equal(&window[0], &window[1]) {
+            run += 1.0;
+        } else {
+            if run > 1.0 {
+                groups.push(run);
+            }
+            run = 1.0;
+        }
+    }
+    if run > 1.0 {
+        groups.push(run);
+    }
+    groups
+}
+
+// Sort `values` ascending, returning how many strictly decreasing pairs it had
+fn merge_count(values: &mut [f64], buffer: &mut [f64]) -> u64 {
+    let n = values.len();
+    if n < 2 {
+        return 0;
+    }
+    let middle = n / 2;
+    let mut swaps = {
+        let (left, right) = values.split_at_mut(middle);
+        let (left_buffer, right_buffer) = buffer.split_at_mut(middle);
+        merge_count(left, left_buffer) + merge_count(right, right_buffer)
+    };
+    let (mut i, mut j, mut k) = (0, middle, 0);
+    while i < middle && j < n {
+        if values[j] < values[i] {
+            swaps += (middle - i) as u64;
+            buffer[k] = values[j];
+            j += 1;
+        } else {
+            buffer[k] = values[i];
+            i += 1;
+        }
+        k += 1;
+    }
+    buffer[k..k + middle - i].copy_from_slice(&values[i..middle]);
+    k += middle - i;
+    buffer[k..n].copy_from_slice(&values[j..n]);
+    values.copy_from_slice(&buffer[..n]);
+    swaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Direct O(n²) count, the definition the fast version must agree with
+    fn brute_force_tau_b(x: &[f64], y: &[f64]) -> f64 {
+        let (mut concordant, mut discordant, mut ties_x, mut ties_y) = (0.0, 0.0, 0.0, 0.0);
+        for i in 0..x.len() {
+            for j in (i + 1)..x.len() {
+                let (dx, dy) = (x[i] - x[j], y[i] - y[j]);
+                if dx == 0.0 && dy == 0.0 {
+                    continue;
+                } else if dx == 0.0 {
+                    ties_x += 1.0;
+                } else if dy == 0.0 {
+                    ties_y += 1.0;
+                } else if dx * dy > 0.0 {
+                    concordant += 1.0;
+                } else {
+                    discordant += 1.0;
+                }
+            }
+        }
+        let f: f64 = concordant + discordant;
+        (concordant - discordant) / ((f + ties_x) * (f + ties_y)).sqrt()
+    }
+
+    #[test]
+    fn pearson_of_a_perfect_line() {
+        assert!((pearson(&[1.0, 2.0, 3.0, 4.0], &[3.0, 5.0, 7.0, 9.0]) - 1.0).abs() < 1e-12);
+        assert!((pearson(&[1.0, 2.0, 3.0, 4.0], &[9.0, 7.0, 5.0, 3.0]) + 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn kendall_matches_the_pairwise_definition_with_ties() {
+        let x = [1.0, 2.0, 2.0, 3.0, 4.0, 4.0, 4.0, 5.0, 6.0, 7.0];
+        let y = [2.0, 1.0, 3.0, 3.0, 5.0, 4.0, 5.0, 8.0, 6.0, 6.0];
+        let (tau, _) = kendall_tau_b(&x, &y);
+        assert!((tau - brute_force_tau_b(&x, &y)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn kendall_without_ties_uses_the_classic_variance() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let y = [2.0, 1.0, 4.0, 3.0, 6.0, 5.0];
+        let (tau, z) = kendall_tau_b(&x, &y);
+        let n: f64 = 6.0;
+        let expected = 3.0 * tau * (n * (n - 1.0)).sqrt() / (2.0 * (2.0 * n + 5.0)).sqrt();
+        assert!((tau - 0.6).abs() < 1e-12);
+        assert!((z - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn kendall_variance_accounts_for_ties() {
+        // Three tied pairs in x: S = 12 and Var(S) = (6·5·17 − 3·2·1·9) / 18
+        let x = [1.0, 1.0, 2.0, 2.0, 3.0, 3.0];
+        let y = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let (tau, z) = kendall_tau_b(&x, &y);
+        assert!((tau - 12.0 / (12.0f64 * 15.0).sqrt()).abs() < 1e-12);
+        assert!((z - 12.0 / (456.0f64 / 18.0).sqrt()).abs() < 1e-12);
+    }
+}
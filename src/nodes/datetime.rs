@@ -0,0 +1,91 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime};
+
+// Formats tried in order when a node does not specify one
+const DATETIME_FORMATS: [&str; 6] = [
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%d/%m/%Y %H:%M:%S",
+];
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%Y/%m/%d", "%d/%m/%Y"];
+
+pub const OUTPUT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+pub const DATE_OUTPUT_FORMAT: &str = "%Y-%m-%d";
+
+// Pass as the format to read spreadsheet serial numbers (days since 1899-12-30, time as the fraction)
+pub const EXCEL_FORMAT: &str = "excel";
+
+// Parse with an explicit chrono format, or fall back to RFC 3339 and the common formats above
+pub fn parse_datetime(value: &str, format: Option<&str>) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    if format == Some(EXCEL_FORMAT) {
+        return value.parse::<f64>().ok().and_then(excel_serial_to_datetime);
+    }
+    if let Some(format) = format {
+        return NaiveDateTime::parse_from_str(value, format)
+            .ok()
+            .or_else(|| NaiveDate::parse_from_str(value, format).ok().and_then(|d| d.and_hms_opt(0, 0, 0)));
+    }
+
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(value) {
+        return Some(parsed.naive_utc());
+    }
+    for format in DATETIME_FORMATS {
+        if let Ok(parsed) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(parsed);
+        }
+    }
+    for format in DATE_FORMATS {
+        if let Ok(parsed) = NaiveDate::parse_from_str(value, format) {
+            return parsed.and_hms_opt(0, 0, 0);
+        }
+    }
+    None
+}
+
+// The 1900 date system counts a nonexistent 1900-02-29, anchoring at 1899-12-30 keeps serials from March 1900 on correct
+pub fn excel_serial_to_datetime(serial: f64) -> Option<NaiveDateTime> {
+    if !(0.0..=2_958_465.0).contains(&serial) {
+        return None;
+    }
+    let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+    let milliseconds = (serial * 86_400_000.0).round() as i64;
+    epoch.checked_add_signed(Duration::milliseconds(milliseconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formatted(value: &str, format: Option<&str>) -> Option<String> {
+        parse_datetime(value, format).map(|d| d.format(OUTPUT_FORMAT).to_string())
+    }
+
+    #[test]
+    fn falls_back_to_the_common_formats() {
+        assert_eq!(formatted("2024-02-29T10:30:00+02:00", None).as_deref(), Some("2024-02-29 08:30:00"));
+        assert_eq!(formatted(" 2024-02-29 10:30 ", None).as_deref(), Some("2024-02-29 10:30:00"));
+        assert_eq!(formatted("29/02/2024", None).as_deref(), Some("2024-02-29 00:00:00"));
+        assert_eq!(formatted("2023-02-29", None), None);
+        assert_eq!(formatted("", None), None);
+    }
+
+    #[test]
+    fn explicit_formats_accept_dates_without_a_time() {
+        assert_eq!(formatted("03.04.2024", Some("%d.%m.%Y")).as_deref(), Some("2024-04-03 00:00:00"));
+        assert_eq!(formatted("2024-04-03", Some("%d.%m.%Y")), None);
+    }
+
+    #[test]
+    fn excel_serials() {
+        assert_eq!(formatted("45351.5", Some(EXCEL_FORMAT)).as_deref(), Some("2024-02-29 12:00:00"));
+        assert_eq!(formatted("61", Some(EXCEL_FORMAT)).as_deref(), Some("1900-03-01 00:00:00"));
+        assert_eq!(excel_serial_to_datetime(-1.0), None);
+    }
+}
@@ -0,0 +1,193 @@
+use nalgebra::{DMatrix, DVector};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use crate::nodes::table::{is_missing, parse_number, Table};
+
+// Predictor matrix shared by the regression nodes
+pub struct DesignMatrix {
+    pub terms: Vec<String>,
+    pub x: DMatrix<f64>,
+    pub y: DVector<f64>,
+    pub row_indices: Vec<usize>, // Rows of the input table that were complete and used in the fit
+    pub categorical: Vec<CategoricalPredictor>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct CategoricalPredictor {
+    pub column: String,
+    pub reference: String,
+    pub levels: Vec<String>,
+}
+
+enum Predictor {
+    Numeric(usize),
+    Categorical(usize, Vec<String>), // Column index and levels, the first level is the reference
+}
+
+impl DesignMatrix {
+    // Build the design matrix, dummy coding any predictor that is non-numeric or listed in `force_categorical`.
+    // Rows with a missing target or predictor are dropped (listwise deletion).
+    pub fn build(
+        table: &Table,
+        target: &str,
+        predictors: &[String],
+        force_categorical: &[String],
+        intercept: bool,
+        parse_target: impl Fn(&str) -> Option<f64>,
+    ) -> Result<DesignMatrix, Box<dyn Error>> {
+        if predictors.is_empty() {
+            return Err("At least one predictor column is required".into());
+        }
+        let target_index = table.column_index(target)?;
+
+        let mut columns: Vec<(usize, bool)> = Vec::new(); // Column index and whether it is categorical
+        for predictor in predictors {
+            if predictor == target {
+                return Err(format!("Column '{}' cannot be both target and predictor", target).into());
+            }
+            let index = table.column_index(predictor)?;
+            let is_numeric = table
+                .rows
+                .iter()
+                .filter_map(|row| row.get(index))
+                .filter(|v| !is_missing(v))
+                .all(|v| parse_number(v).is_some());
+            columns.push((index, !is_numeric || force_categorical.contains(predictor)));
+        }
+
+        // Listwise deletion first, so levels only seen in dropped rows do not become all-zero dummies
+        let mut row_indices: Vec<usize> = Vec::new();
+        let mut targets: Vec<f64> = Vec::new();
+        for (row_index, row) in table.rows.iter().enumerate() {
+            let y = match row.get(target_index).and_then(|v| parse_target(v)) {
+                Some(y) => y,
+                None => continue,
+            };
+            let complete = columns.iter().all(|(index, is_categorical)| match row.get(*index) {
+                Some(v) if *is_categorical => !is_missing(v),
+                Some(v) => parse_number(v).is_some(),
+                None => false,
+            });
+            if complete {
+                row_indices.push(row_index);
+                targets.push(y);
+            }
+        }
+
+        let mut specs: Vec<Predictor> = Vec::new();
+        let mut terms: Vec<String> = Vec::new();
+        let mut categorical: Vec<CategoricalPredictor> = Vec::new();
+        if intercept {
+            terms.push("(Intercept)".to_string());
+        }
+        for (predictor, (index, is_categorical)) in predictors.iter().zip(&columns) {
+            if !is_categorical {
+                terms.push(predictor.clone());
+                specs.push(Predictor::Numeric(*index));
+                continue;
+            }
+            let levels: Vec<String> = row_indices
+                .iter()
+                .map(|r| table.rows[*r][*index].trim().to_string())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            if levels.len() < 2 {
+                return Err(format!("Categorical predictor '{}' needs at least two levels among the complete rows", predictor).into());
+            }
+            for level in levels.iter().skip(1) {
+                terms.push(format!("{}[{}]", predictor, level));
+            }
+            categorical.push(CategoricalPredictor {
+                column: predictor.clone(),
+                reference: levels[0].clone(),
+                levels: levels.clone(),
+            });
+            specs.push(Predictor::Categorical(*index, levels));
+        }
+
+        let mut data: Vec<f64> = Vec::with_capacity(row_indices.len() * terms.len());
+        for row_index in &row_indices {
+            let row = &table.rows[*row_index];
+            if intercept {
+                data.push(1.0);
+            }
+            for spec in &specs {
+                match spec {
+                    Predictor::Numeric(index) => data.push(parse_number(&row[*index]).unwrap_or_default()),
+                    Predictor::Categorical(index, levels) => {
+                        let value = row[*index].trim();
+                        data.extend(levels.iter().skip(1).map(|level| if level == value { 1.0 } else { 0.0 }));
+                    }
+                }
+            }
+        }
+
+        if row_indices.len() <= terms.len() {
+            return Err(format!(
+                "Not enough complete rows ({}) to estimate {} coefficients",
+                row_indices.len(),
+                terms.len()
+            )
+            .into());
+        }
+
+        let x = DMatrix::from_row_slice(row_indices.len(), terms.len(), &data);
+        let y = DVector::from_vec(targets);
+
+        Ok(DesignMatrix { terms, x, y, row_indices, categorical })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(rows: &[&[&str]]) -> Table {
+        Table {
+            headers: vec!["y".to_string(), "x".to_string(), "group".to_string()],
+            rows: rows.iter().map(|r| r.iter().map(|v| v.to_string()).collect()).collect(),
+        }
+    }
+
+    #[test]
+    fn dummy_codes_categorical_predictors_against_the_first_level() {
+        let table = table(&[&["1", "1", "b"], &["2", "2", "a"], &["3", "3", "c"], &["4", "5", "a"], &["5", "4", "b"]]);
+        let predictors = vec!["x".to_string(), "group".to_string()];
+        let design = DesignMatrix::build(&table, "y", &predictors, &[], true, parse_number).unwrap();
+
+        assert_eq!(design.terms, vec!["(Intercept)", "x", "group[b]", "group[c]"]);
+        assert_eq!(design.categorical[0].reference, "a");
+        assert_eq!(design.x.row(0).iter().copied().collect::<Vec<_>>(), vec![1.0, 1.0, 1.0, 0.0]);
+        assert_eq!(design.x.row(1).iter().copied().collect::<Vec<_>>(), vec![1.0, 2.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn levels_come_from_complete_rows_only() {
+        // `c` only appears in a row whose target is missing, it must not become an all-zero dummy
+        let table = table(&[&["1", "1", "a"], &["2", "2", "b"], &["NA", "3", "c"], &["4", "4", "a"], &["5", "6", "b"]]);
+        let predictors = vec!["x".to_string(), "group".to_string()];
+        let design = DesignMatrix::build(&table, "y", &predictors, &[], true, parse_number).unwrap();
+
+        assert_eq!(design.terms, vec!["(Intercept)", "x", "group[b]"]);
+        assert_eq!(design.categorical[0].levels, vec!["a", "b"]);
+        assert_eq!(design.row_indices, vec![0, 1, 3, 4]);
+    }
+
+    #[test]
+    fn rejects_a_categorical_with_one_level_left() {
+        let table = table(&[&["1", "1", "a"], &["2", "2", "a"], &["", "3", "b"], &["4", "4", "a"]]);
+        let predictors = vec!["group".to_string()];
+        assert!(DesignMatrix::build(&table, "y", &predictors, &[], true, parse_number).is_err());
+    }
+
+    #[test]
+    fn forced_categorical_numeric_column() {
+        let table = table(&[&["1", "1", "a"], &["2", "2", "a"], &["3", "1", "a"], &["4", "2", "a"]]);
+        let predictors = vec!["x".to_string()];
+        let design = DesignMatrix::build(&table, "y", &predictors, &["x".to_string()], false, parse_number).unwrap();
+        assert_eq!(design.terms, vec!["x[2]"]);
+    }
+}
@@ -0,0 +1,309 @@
+use csv::{Reader, ReaderBuilder};
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+
+// How an uploaded file is laid out. It is stored next to the upload as `<file>.dialect.json` and
+// every node reads through `open_reader`; node outputs have no sidecar and use the defaults.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Dialect {
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    #[serde(default = "default_quote")]
+    pub quote: char,
+    #[serde(default = "default_has_headers")]
+    pub has_headers: bool, // Without a header row the columns are named `column_1`, `column_2`, ...
+    #[serde(default = "default_encoding")]
+    pub encoding: String, // WHATWG label, e.g. `utf-8` or `latin1`
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_quote() -> char {
+    '"'
+}
+
+fn default_has_headers() -> bool {
+    true
+}
+
+fn default_encoding() -> String {
+    "utf-8".to_string()
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect {
+            delimiter: default_delimiter(),
+            quote: default_quote(),
+            has_headers: default_has_headers(),
+            encoding: default_encoding(),
+        }
+    }
+}
+
+// Partial dialect sent by the user, unset fields keep the sniffed values
+#[derive(Deserialize, Default, Clone)]
+pub struct DialectOverride {
+    pub delimiter: Option<char>,
+    pub quote: Option<char>,
+    pub has_headers: Option<bool>,
+    pub encoding: Option<String>,
+}
+
+const DELIMITER_CANDIDATES: [u8; 4] = [b',', b';', b'\t', b'|'];
+const SNIFF_BYTES: usize = 64 * 1024;
+const SNIFF_ROWS: usize = 50;
+
+impl Dialect {
+    pub fn apply(&mut self, changes: DialectOverride) -> Result<(), Box<dyn Error>> {
+        if let Some(delimiter) = changes.delimiter {
+            self.delimiter = delimiter;
+        }
+        if let Some(quote) = changes.quote {
+            self.quote = quote;
+        }
+        if let Some(has_headers) = changes.has_headers {
+            self.has_headers = has_headers;
+        }
+        if let Some(encoding) = changes.encoding {
+            self.encoding = encoding;
+        }
+        self.validate()
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if !self.delimiter.is_ascii() || !self.quote.is_ascii() {
+            return Err("Delimiter and quote must be single ASCII characters".into());
+        }
+        if Encoding::for_label(self.encoding.as_bytes()).is_none() {
+            return Err(format!("Unknown encoding '{}'", self.encoding).into());
+        }
+        Ok(())
+    }
+}
+
+pub fn sidecar_path(file_path: &str) -> String {
+    format!("{}.dialect.json", file_path)
+}
+
+// The stored dialect of a file, the default dialect when none was stored
+pub fn load(file_path: &str) -> Dialect {
+    std::fs::read_to_string(sidecar_path(file_path))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn save(file_path: &str, dialect: &Dialect) -> Result<(), Box<dyn Error>> {
+    std::fs::write(sidecar_path(file_path), serde_json::to_string_pretty(dialect)?)?;
+    Ok(())
+}
+
+// Sniff the dialect from the start of the file and store it next to the file
+pub fn detect_and_save(file_path: &str) -> Result<Dialect, Box<dyn Error>> {
+    let mut sample = Vec::with_capacity(SNIFF_BYTES);
+    File::open(file_path)?.take(SNIFF_BYTES as u64).read_to_end(&mut sample)?;
+    let dialect = sniff(&sample);
+    save(file_path, &dialect)?;
+    Ok(dialect)
+}
+
+pub fn sniff(sample: &[u8]) -> Dialect {
+    let encoding = detect_encoding(sample);
+    let (text, _, _) = encoding.decode(sample);
+    // Drop the last line, it is likely cut off by the sample size
+    let text = match text.rfind('\n') {
+        Some(end) if sample.len() >= SNIFF_BYTES => &text[..end],
+        _ => &text[..],
+    };
+
+    // An apostrophe inside a word ("it's") says nothing, only quotes wrapping whole fields count
+    let quote = if quoted_fields(text, '\'') > quoted_fields(text, '"') { b'\'' } else { b'"' };
+    let delimiter = DELIMITER_CANDIDATES
+        .iter()
+        .copied()
+        .max_by_key(|d| delimiter_score(text, *d, quote))
+        .unwrap_or(b',');
+    let rows = sample_rows(text, delimiter, quote);
+
+    Dialect {
+        delimiter: delimiter as char,
+        quote: quote as char,
+        has_headers: looks_like_header(&rows),
+        encoding: encoding.name().to_lowercase(),
+    }
+}
+
+// How many fields look wrapped in `quote`: the smaller of the quotes opening a field (line start or
+// a candidate delimiter before it) and those closing one (line end or a candidate delimiter after it)
+fn quoted_fields(text: &str, quote: char) -> usize {
+    let is_boundary = |c: Option<char>| match c {
+        None | Some('\n') | Some('\r') => true,
+        Some(c) => c.is_ascii() && DELIMITER_CANDIDATES.contains(&(c as u8)),
+    };
+    let (mut opening, mut closing) = (0, 0);
+    let mut previous: Option<char> = None;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        if c == quote {
+            if is_boundary(previous) && !is_boundary(next) {
+                opening += 1;
+            } else if is_boundary(next) && !is_boundary(previous) {
+                closing += 1;
+            }
+        }
+        previous = Some(c);
+    }
+    opening.min(closing)
+}
+
+// Valid UTF-8 (a trailing cut-off character is fine) or a BOM means UTF-8, anything else is treated as Latin-1
+fn detect_encoding(sample: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => UTF_8,
+        Err(err) if err.error_len().is_none() => UTF_8,
+        Err(_) => WINDOWS_1252,
+    }
+}
+
+fn sample_rows(text: &str, delimiter: u8, quote: u8) -> Vec<Vec<String>> {
+    ReaderBuilder::new()
+        .delimiter(delimiter)
+        .quote(quote)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .records()
+        .take(SNIFF_ROWS)
+        .filter_map(|r| r.ok())
+        .map(|r| r.iter().map(|v| v.to_string()).collect())
+        .collect()
+}
+
+// Rows agreeing on the most common field count, preferring delimiters that split rows into more fields
+fn delimiter_score(text: &str, delimiter: u8, quote: u8) -> (usize, usize) {
+    let rows = sample_rows(text, delimiter, quote);
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for row in &rows {
+        *counts.entry(row.len()).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .filter(|(fields, _)| *fields > 1)
+        .map(|(fields, rows)| (rows, fields))
+        .max()
+        .unwrap_or((0, 0))
+}
+
+// A header row has no numbers and no repeated names
+fn looks_like_header(rows: &[Vec<String>]) -> bool {
+    let Some(first) = rows.first() else {
+        return true;
+    };
+    if first.iter().any(|v| v.trim().parse::<f64>().is_ok()) {
+        return false;
+    }
+    let names: Vec<&str> = first.iter().map(|v| v.trim()).filter(|v| !v.is_empty()).collect();
+    let mut distinct = names.clone();
+    distinct.sort();
+    distinct.dedup();
+    !names.is_empty() && distinct.len() == names.len()
+}
+
+// CSV reader that honours the stored dialect of `file_path`
+pub fn open_reader(file_path: &str) -> Result<Reader<Box<dyn Read>>, Box<dyn Error>> {
+    let dialect = load(file_path);
+    let encoding = Encoding::for_label(dialect.encoding.as_bytes()).unwrap_or(UTF_8);
+    let builder = |has_headers: bool| {
+        let mut builder = ReaderBuilder::new();
+        builder.delimiter(dialect.delimiter as u8).quote(dialect.quote as u8).has_headers(has_headers);
+        builder
+    };
+
+    let mut source = utf8_source(file_path, encoding)?;
+    if !dialect.has_headers {
+        // Put a generated header row in front so every reader sees the same column names
+        let width = builder(false).from_reader(utf8_source(file_path, encoding)?).headers()?.len();
+        let names: Vec<String> = (1..=width).map(|i| format!("column_{}", i)).collect();
+        let header_line = format!("{}\n", names.join(&dialect.delimiter.to_string()));
+        source = Box::new(Cursor::new(header_line.into_bytes()).chain(source));
+    }
+    Ok(builder(true).from_reader(source))
+}
+
+fn utf8_source(file_path: &str, encoding: &'static Encoding) -> Result<Box<dyn Read>, Box<dyn Error>> {
+    let mut file = File::open(file_path)?;
+    let mut start = Vec::with_capacity(3);
+    (&mut file).take(3).read_to_end(&mut start)?;
+    if encoding == UTF_8 && Encoding::for_bom(&start).is_none() {
+        return Ok(Box::new(Cursor::new(start).chain(BufReader::new(file))));
+    }
+    // Transcode to UTF-8 while reading, a BOM wins over the stored encoding and is stripped
+    let decoder = DecodeReaderBytesBuilder::new()
+        .encoding(Some(encoding))
+        .bom_override(true)
+        .build(Cursor::new(start).chain(BufReader::new(file)));
+    Ok(Box::new(decoder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::write_csv;
+
+    #[test]
+    fn sniffs_semicolons_and_headers() {
+        let dialect = sniff(b"name;score\nann;1,5\nbob;2,5\n");
+        assert_eq!((dialect.delimiter, dialect.quote, dialect.has_headers), (';', '"', true));
+        assert!(!sniff(b"1,2\n3,4\n").has_headers);
+    }
+
+    #[test]
+    fn a_lone_apostrophe_does_not_become_the_quote() {
+        assert_eq!(sniff(b"name,note\nann,it's fine\nbob,ok\n").quote, '"');
+        assert_eq!(sniff(b"name,note\nann,'a, b'\nbob,'c'\n").quote, '\'');
+    }
+
+    #[test]
+    fn latin1_files_are_transcoded_while_reading() {
+        let path = write_csv("dialect_latin1", "");
+        std::fs::write(&path, b"name,city\nJos\xe9,M\xfcnchen\n").unwrap();
+        let dialect = detect_and_save(&path).unwrap();
+        assert_eq!(dialect.encoding, "windows-1252");
+
+        let mut reader = open_reader(&path).unwrap();
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(record.iter().collect::<Vec<_>>(), vec!["Jos\u{e9}", "M\u{fc}nchen"]);
+    }
+
+    #[test]
+    fn a_bom_is_stripped_and_headers_are_generated() {
+        let path = write_csv("dialect_bom", "\u{feff}1|2\n3|4\n");
+        let dialect = detect_and_save(&path).unwrap();
+        assert_eq!((dialect.delimiter, dialect.has_headers), ('|', false));
+
+        let mut reader = open_reader(&path).unwrap();
+        assert_eq!(reader.headers().unwrap().iter().collect::<Vec<_>>(), vec!["column_1", "column_2"]);
+        let rows: Vec<Vec<String>> = reader.records().map(|r| r.unwrap().iter().map(|v| v.to_string()).collect()).collect();
+        assert_eq!(rows, vec![vec!["1", "2"], vec!["3", "4"]]);
+    }
+
+    #[test]
+    fn overrides_are_validated() {
+        let apply = |changes: DialectOverride| Dialect::default().apply(changes);
+        assert!(apply(DialectOverride { encoding: Some("klingon".to_string()), ..Default::default() }).is_err());
+        assert!(apply(DialectOverride { delimiter: Some('\u{b6}'), ..Default::default() }).is_err());
+        assert!(apply(DialectOverride { encoding: Some("latin1".to_string()), ..Default::default() }).is_ok());
+    }
+}
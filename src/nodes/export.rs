@@ -0,0 +1,276 @@
+use csv::WriterBuilder;
+use rust_xlsxwriter::Workbook;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::nodes::columnar;
+use crate::nodes::dialect::{self, open_reader, Dialect};
+use crate::nodes::output_parquet::export_parquet;
+use crate::nodes::table::parse_number;
+
+// Excel sheet limits, including the header row
+const XLSX_MAX_ROWS: usize = 1_048_576;
+const XLSX_MAX_COLUMNS: usize = 16_384;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    Xlsx,
+    Parquet,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Result<ExportFormat, String> {
+        match format.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "tsv" => Ok(ExportFormat::Tsv),
+            "xlsx" => Ok(ExportFormat::Xlsx),
+            "parquet" => Ok(ExportFormat::Parquet),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(format!("Unknown format '{}', use csv, tsv, xlsx, parquet or json", other)),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Tsv => "tsv",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Tsv => "text/tab-separated-values; charset=utf-8",
+            ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+            ExportFormat::Json => "application/json",
+        }
+    }
+}
+
+// Where the export of a node output is written. The node and port come from the request and may hold
+// any characters (ports are archive member names), so the file is named by a digest of them instead.
+pub fn export_path(run_id: &str, node_id: &str, port: &str, format: ExportFormat) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(node_id.as_bytes());
+    hasher.update([0]);
+    hasher.update(port.as_bytes());
+    let digest = format!("{:x}", hasher.finalize());
+    format!("./storage_bin/exports/{}_{}.{}", run_id, &digest[..16], format.extension())
+}
+
+// File name offered in Content-Disposition, anything outside `[A-Za-z0-9_-]` becomes `_`
+pub fn download_name(node_id: &str, port: &str, format: ExportFormat) -> String {
+    let name = if port.is_empty() { format!("node_{}", node_id) } else { format!("node_{}_{}", node_id, port) };
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == '-' { c } else { '_' })
+        .collect();
+    format!("{}.{}", name, format.extension())
+}
+
+// Write through a temporary file renamed into place, so a concurrent request writing the same path
+// never truncates a file that is still being streamed to a client
+pub fn write_atomically<T>(output_file: &str, write: impl FnOnce(&str) -> Result<T, Box<dyn Error>>) -> Result<T, Box<dyn Error>> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let temporary = format!("{}.{}-{}.tmp", output_file, std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed));
+    match write(&temporary) {
+        Ok(written) => {
+            std::fs::rename(&temporary, output_file)?;
+            Ok(written)
+        }
+        Err(err) => {
+            let _ = std::fs::remove_file(&temporary);
+            Err(err)
+        }
+    }
+}
+
+// Numbers with a leading zero ("00042") are codes such as zip codes or IDs, Excel would drop the zeros
+fn has_leading_zero(value: &str) -> bool {
+    let digits = value.trim().trim_start_matches(['-', '+']);
+    digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.")
+}
+
+// The file to send for `source` in `format`: the source itself when it already is that file,
+// otherwise a converted copy written to `output_file`
+pub fn export(source: &str, format: ExportFormat, output_file: &str) -> Result<String, Box<dyn Error>> {
+    if columnar::is_columnar(source) {
+        if format == ExportFormat::Parquet && source.to_lowercase().ends_with(".parquet") {
+            return Ok(source.to_string());
+        }
+        let (csv_path, _) = columnar::convert_to_csv(source)?;
+        return export(&csv_path, format, output_file);
+    }
+    // Node outputs are plain CSV already, uploads may be in another dialect
+    if format == ExportFormat::Csv && dialect::load(source) == Dialect::default() {
+        return Ok(source.to_string());
+    }
+
+    if let Some(parent) = Path::new(output_file).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_atomically(output_file, |path| match format {
+        ExportFormat::Csv => write_delimited(source, b',', path),
+        ExportFormat::Tsv => write_delimited(source, b'\t', path),
+        ExportFormat::Xlsx => write_xlsx(source, path),
+        ExportFormat::Parquet => export_parquet(source, path),
+        ExportFormat::Json => write_json(source, path),
+    })?;
+    println!("{} exported to {}", source, output_file);
+    Ok(output_file.to_string())
+}
+
+fn write_delimited(source: &str, delimiter: u8, output_file: &str) -> Result<(), Box<dyn Error>> {
+    let mut reader = open_reader(source)?;
+    let mut writer = WriterBuilder::new().delimiter(delimiter).from_path(output_file)?;
+    writer.write_record(reader.headers()?)?;
+    for record in reader.records() {
+        writer.write_record(&record?)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// Array of row objects as `Output_CSV` returns them, written record by record
+fn write_json(source: &str, output_file: &str) -> Result<(), Box<dyn Error>> {
+    let mut reader = open_reader(source)?;
+    let headers = reader.headers()?.clone();
+    let mut writer = BufWriter::new(File::create(output_file)?);
+    writer.write_all(b"[")?;
+    for (index, record) in reader.records().enumerate() {
+        let record = record?;
+        let row: serde_json::Value = headers.iter().zip(record.iter()).collect();
+        if index > 0 {
+            writer.write_all(b",\n")?;
+        }
+        serde_json::to_writer(&mut writer, &row)?;
+    }
+    writer.write_all(b"]\n")?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Numeric cells become Excel numbers, everything else (and numbers with leading zeros) text
+fn write_xlsx(source: &str, output_file: &str) -> Result<(), Box<dyn Error>> {
+    let mut reader = open_reader(source)?;
+    let headers = reader.headers()?.clone();
+    if headers.len() > XLSX_MAX_COLUMNS {
+        return Err(format!("{} columns do not fit in an Excel sheet, the limit is {}", headers.len(), XLSX_MAX_COLUMNS).into());
+    }
+
+    let mut workbook = Workbook::new();
+    let sheet = workbook.add_worksheet_with_constant_memory();
+    for (column, name) in headers.iter().enumerate() {
+        sheet.write_string(0, column as u16, name)?;
+    }
+    for (index, record) in reader.records().enumerate() {
+        let row = index + 1;
+        if row >= XLSX_MAX_ROWS {
+            return Err(format!("The table has more rows than an Excel sheet holds ({}), download it as CSV or Parquet", XLSX_MAX_ROWS - 1).into());
+        }
+        for (column, value) in record?.iter().enumerate() {
+            match parse_number(value) {
+                Some(number) if !has_leading_zero(value) => sheet.write_number(row as u32, column as u16, number)?,
+                _ if value.is_empty() => continue,
+                _ => sheet.write_string(row as u32, column as u16, value)?,
+            };
+        }
+    }
+    workbook.save(output_file)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::{storage_bin, write_csv};
+
+    #[test]
+    fn export_paths_stay_in_the_exports_directory() {
+        let path = export_path("20240101", "../../etc", "../passwd", ExportFormat::Csv);
+        let name = path.strip_prefix("./storage_bin/exports/20240101_").unwrap();
+        assert_eq!(name.len(), "0123456789abcdef.csv".len());
+        assert!(name.trim_end_matches(".csv").chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(export_path("1", "2", "", ExportFormat::Csv), export_path("1", "2", "test", ExportFormat::Csv));
+        assert_ne!(export_path("1", "2a", "", ExportFormat::Csv), export_path("1", "2", "a", ExportFormat::Csv));
+    }
+
+    #[test]
+    fn download_names_cannot_break_the_header() {
+        assert_eq!(download_name("7", "", ExportFormat::Xlsx), "node_7.xlsx");
+        assert_eq!(download_name("7", "test", ExportFormat::Csv), "node_7_test.csv");
+        assert_eq!(download_name("up", "../a\"; x=\"y.csv", ExportFormat::Json), "node_up____a___x__y_csv.json");
+    }
+
+    #[test]
+    fn leading_zeros_mark_codes() {
+        assert!(has_leading_zero("00042"));
+        assert!(has_leading_zero("-007"));
+        assert!(!has_leading_zero("0"));
+        assert!(!has_leading_zero("0.5"));
+        assert!(!has_leading_zero("42"));
+    }
+
+    #[test]
+    fn xlsx_keeps_leading_zeros_as_text() {
+        use calamine::{open_workbook_auto, Data, Reader};
+        storage_bin();
+        let source = write_csv("export_xlsx", "zip,amount\n00042,0.5\n10115,0\n");
+        let path = export(&source, ExportFormat::Xlsx, &export_path("test", "export", "xlsx", ExportFormat::Xlsx)).unwrap();
+        let mut workbook = open_workbook_auto(&path).unwrap();
+        let sheet = workbook.sheet_names()[0].clone();
+        let cells = workbook.worksheet_range(&sheet).unwrap();
+        assert_eq!(cells.get_value((1, 0)), Some(&Data::String("00042".to_string())));
+        assert_eq!(cells.get_value((1, 1)), Some(&Data::Float(0.5)));
+        assert_eq!(cells.get_value((2, 0)), Some(&Data::Float(10115.0)));
+    }
+
+    #[test]
+    fn concurrent_exports_never_expose_a_partial_file() {
+        storage_bin();
+        let source = write_csv("export_concurrent", &format!("x\n{}", "1\n".repeat(20_000)));
+        let output = export_path("test", "export", "concurrent", ExportFormat::Tsv);
+        let expected = std::fs::read_to_string(&source).unwrap();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| export(&source, ExportFormat::Tsv, &output).unwrap());
+            }
+            for _ in 0..50 {
+                if let Ok(contents) = std::fs::read_to_string(&output) {
+                    assert_eq!(contents, expected);
+                }
+            }
+        });
+        let leftovers = std::fs::read_dir("./storage_bin/exports")
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().ends_with(".tmp"))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn exports_tsv_and_json() {
+        storage_bin();
+        let source = write_csv("export_source", "name,score\n\"a\tb\",1.5\nc,\n");
+        let tsv = export(&source, ExportFormat::Tsv, &export_path("test", "export", "tsv", ExportFormat::Tsv)).unwrap();
+        assert_eq!(std::fs::read_to_string(tsv).unwrap(), "name\tscore\n\"a\tb\"\t1.5\nc\t\n");
+
+        let json = export(&source, ExportFormat::Json, &export_path("test", "export", "json", ExportFormat::Json)).unwrap();
+        let rows: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(json).unwrap()).unwrap();
+        assert_eq!(rows, serde_json::json!([{ "name": "a\tb", "score": "1.5" }, { "name": "c", "score": "" }]));
+
+        // Plain CSV in the default dialect is sent as is
+        assert_eq!(export(&source, ExportFormat::Csv, "unused.csv").unwrap(), source);
+    }
+}
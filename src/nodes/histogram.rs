@@ -0,0 +1,344 @@
+use serde::Deserialize;
+use serde_json::json;
+use std::error::Error;
+
+use crate::nodes::descriptive::{quantile_sorted, sorted, std_dev};
+use crate::nodes::table::Table;
+
+pub struct Histogram;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+enum BinMethod {
+    Count,
+    Width,
+    FreedmanDiaconis,
+    Sturges,
+}
+
+#[derive(Deserialize)]
+struct HistogramConfig {
+    columns: Vec<String>,
+    #[serde(default = "default_method")]
+    method: BinMethod,
+    #[serde(default)]
+    bins: Option<usize>, // Used by the `count` method
+    #[serde(default)]
+    width: Option<f64>, // Used by the `width` method
+    #[serde(default = "default_true")]
+    density: bool,
+    #[serde(default = "default_density_points")]
+    density_points: usize,
+    #[serde(default)]
+    bandwidth: Option<f64>, // Silverman's rule of thumb when not set
+    #[serde(default = "default_true")]
+    boxplot: bool,
+    #[serde(default = "default_max_outliers")]
+    max_outliers: usize, // Cap on the outlier values sent for the box plot
+}
+
+// Upper bound on bins and density points so a request cannot allocate without limit
+const MAX_BINS: usize = 10_000;
+const MAX_DENSITY_POINTS: usize = 10_000;
+// Past this many values the density is estimated from counts on a grid of this many bins,
+// so the cost is bounded by bins × points instead of growing with the row count
+const DENSITY_BINS: usize = 2_048;
+
+fn default_method() -> BinMethod {
+    BinMethod::Sturges
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_density_points() -> usize {
+    128
+}
+
+fn default_max_outliers() -> usize {
+    100
+}
+
+impl Histogram {
+    // Summarise numeric columns into chart data so the frontend never has to receive the rows
+    pub fn process_node(&self, file1: &str, data: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+        let config: HistogramConfig = serde_json::from_str(data)?;
+        if config.density_points > MAX_DENSITY_POINTS {
+            return Err(format!("At most {} density points are allowed", MAX_DENSITY_POINTS).into());
+        }
+        let table = Table::from_path(file1)?;
+
+        let mut charts: Vec<serde_json::Value> = Vec::new();
+        for column in &config.columns {
+            let values: Vec<f64> = table
+                .numeric_column(table.column_index(column)?)
+                .into_iter()
+                .flatten()
+                .collect();
+            if values.is_empty() {
+                return Err(format!("Column '{}' has no numeric values", column).into());
+            }
+            let values = sorted(&values);
+
+            let mut chart = json!({
+                "column": column,
+                "n": values.len(),
+                "n_missing": table.rows.len() - values.len(),
+                "histogram": histogram(&values, &config)?,
+            });
+            if config.density {
+                chart["density"] = kernel_density(&values, config.bandwidth, config.density_points);
+            }
+            if config.boxplot {
+                chart["boxplot"] = five_number_summary(&values, config.max_outliers);
+            }
+            charts.push(chart);
+        }
+
+        Ok(json!({ "charts": charts }))
+    }
+}
+
+fn histogram(sorted_values: &[f64], config: &HistogramConfig) -> Result<serde_json::Value, Box<dyn Error>> {
+    let n = sorted_values.len();
+    let min = sorted_values[0];
+    let max = sorted_values[n - 1];
+    let range = max - min;
+
+    let sturges = ((n as f64).log2().ceil() as usize + 1).max(1);
+    let bin_count = if range == 0.0 {
+        1
+    } else {
+        match config.method {
+            BinMethod::Count => {
+                let bins = config.bins.ok_or("The count method needs `bins`")?.max(1);
+                if bins > MAX_BINS {
+                    return Err(format!("At most {} bins are allowed, got {}", MAX_BINS, bins).into());
+                }
+                bins
+            }
+            BinMethod::Width => {
+                let width = config.width.ok_or("The width method needs `width`")?;
+                if width <= 0.0 {
+                    return Err("Bin width must be positive".into());
+                }
+                let bins = (range / width).ceil().max(1.0);
+                if bins > MAX_BINS as f64 {
+                    return Err(format!("A width of {} gives {} bins, at most {} are allowed", width, bins, MAX_BINS).into());
+                }
+                bins as usize
+            }
+            BinMethod::FreedmanDiaconis => {
+                let iqr = quantile_sorted(sorted_values, 0.75) - quantile_sorted(sorted_values, 0.25);
+                if iqr > 0.0 {
+                    // A narrow IQR with far outliers asks for huge counts, the rule is only a suggestion so clamp it
+                    let width = 2.0 * iqr / (n as f64).cbrt();
+                    (range / width).ceil().clamp(1.0, MAX_BINS as f64) as usize
+                } else {
+                    sturges
+                }
+            }
+            BinMethod::Sturges => sturges,
+        }
+    };
+    let bin_width = match config.method {
+        BinMethod::Width if range > 0.0 => config.width.unwrap_or(1.0),
+        _ if range > 0.0 => range / bin_count as f64,
+        _ => 1.0,
+    };
+
+    let mut counts = vec![0usize; bin_count];
+    for value in sorted_values {
+        // The last bin is closed on the right so the maximum is counted
+        let index = (((value - min) / bin_width).floor() as usize).min(bin_count - 1);
+        counts[index] += 1;
+    }
+
+    let bins: Vec<serde_json::Value> = counts
+        .iter()
+        .enumerate()
+        .map(|(i, count)| {
+            json!({
+                "lower": min + i as f64 * bin_width,
+                "upper": min + (i + 1) as f64 * bin_width,
+                "count": count,
+                "density": *count as f64 / (n as f64 * bin_width),
+            })
+        })
+        .collect();
+
+    Ok(json!({ "bin_width": bin_width, "bins": bins }))
+}
+
+// Gaussian kernel density evaluated on an evenly spaced grid
+fn kernel_density(sorted_values: &[f64], bandwidth: Option<f64>, points: usize) -> serde_json::Value {
+    let n = sorted_values.len() as f64;
+    let bandwidth = bandwidth.filter(|h| *h > 0.0).unwrap_or_else(|| {
+        let iqr = quantile_sorted(sorted_values, 0.75) - quantile_sorted(sorted_values, 0.25);
+        let spread = match std_dev(sorted_values) {
+            sd if iqr > 0.0 && sd.is_finite() => sd.min(iqr / 1.34),
+            sd if sd.is_finite() && sd > 0.0 => sd,
+            _ => 1.0,
+        };
+        0.9 * spread * n.powf(-0.2)
+    });
+
+    let min = sorted_values[0];
+    let max = sorted_values[sorted_values.len() - 1];
+    let (method, weighted) = if sorted_values.len() > DENSITY_BINS {
+        ("binned", bin_for_density(sorted_values, min, max))
+    } else {
+        ("exact", sorted_values.iter().map(|v| (*v, 1.0)).collect())
+    };
+
+    let start = min - 3.0 * bandwidth;
+    let end = max + 3.0 * bandwidth;
+    let points = points.max(2);
+    let step = (end - start) / (points - 1) as f64;
+    let normaliser = n * bandwidth * (2.0 * std::f64::consts::PI).sqrt();
+
+    let curve: Vec<serde_json::Value> = (0..points)
+        .map(|i| {
+            let x = start + i as f64 * step;
+            let y = weighted
+                .iter()
+                .map(|(v, weight)| weight * (-0.5 * ((x - v) / bandwidth).powi(2)).exp())
+                .sum::<f64>()
+                / normaliser;
+            json!({ "x": x, "y": y })
+        })
+        .collect();
+
+    json!({ "bandwidth": bandwidth, "method": method, "points": curve })
+}
+
+// Counts of the values rounded to the nearest of `DENSITY_BINS` evenly spaced centers,
+// empty bins are left out
+fn bin_for_density(sorted_values: &[f64], min: f64, max: f64) -> Vec<(f64, f64)> {
+    let spacing = (max - min) / (DENSITY_BINS - 1) as f64;
+    if spacing <= 0.0 {
+        return vec![(min, sorted_values.len() as f64)];
+    }
+    let mut counts = vec![0usize; DENSITY_BINS];
+    for value in sorted_values {
+        let index = (((value - min) / spacing).round() as usize).min(DENSITY_BINS - 1);
+        counts[index] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .filter(|(_, count)| *count > 0)
+        .map(|(i, count)| (min + i as f64 * spacing, count as f64))
+        .collect()
+}
+
+// Five number summary with Tukey whiskers at 1.5 IQR
+fn five_number_summary(sorted_values: &[f64], max_outliers: usize) -> serde_json::Value {
+    let q1 = quantile_sorted(sorted_values, 0.25);
+    let q3 = quantile_sorted(sorted_values, 0.75);
+    let iqr = q3 - q1;
+    let lower_fence = q1 - 1.5 * iqr;
+    let upper_fence = q3 + 1.5 * iqr;
+
+    let inside: Vec<f64> = sorted_values
+        .iter()
+        .copied()
+        .filter(|v| *v >= lower_fence && *v <= upper_fence)
+        .collect();
+    let outliers: Vec<f64> = sorted_values
+        .iter()
+        .copied()
+        .filter(|v| *v < lower_fence || *v > upper_fence)
+        .collect();
+
+    json!({
+        "min": sorted_values[0],
+        "q1": q1,
+        "median": quantile_sorted(sorted_values, 0.5),
+        "q3": q3,
+        "max": sorted_values[sorted_values.len() - 1],
+        "lower_whisker": inside.first().copied().unwrap_or(q1),
+        "upper_whisker": inside.last().copied().unwrap_or(q3),
+        "n_outliers": outliers.len(),
+        "outliers": outliers.into_iter().take(max_outliers).collect::<Vec<f64>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(extra: &str) -> HistogramConfig {
+        serde_json::from_str(&format!(r#"{{"columns": ["x"]{}}}"#, extra)).unwrap()
+    }
+
+    fn counts(chart: &serde_json::Value) -> Vec<u64> {
+        chart["bins"].as_array().unwrap().iter().map(|b| b["count"].as_u64().unwrap()).collect()
+    }
+
+    #[test]
+    fn fixed_width_bins_count_the_maximum_in_the_last_bin() {
+        let values = [0.0, 0.5, 1.0, 1.5, 2.0];
+        let chart = histogram(&values, &config(r#", "method": "width", "width": 1.0"#)).unwrap();
+        assert_eq!(chart["bin_width"], 1.0);
+        assert_eq!(counts(&chart), vec![2, 3]);
+    }
+
+    #[test]
+    fn sturges_uses_log2_of_n_plus_one() {
+        let values: Vec<f64> = (0..16).map(|v| v as f64).collect();
+        let chart = histogram(&values, &config("")).unwrap();
+        assert_eq!(counts(&chart).len(), 5);
+        assert_eq!(counts(&chart).iter().sum::<u64>(), 16);
+    }
+
+    #[test]
+    fn bin_counts_are_capped() {
+        let values = [0.0, 1.0];
+        assert!(histogram(&values, &config(r#", "method": "count", "bins": 1000000000000"#)).is_err());
+        assert!(histogram(&values, &config(r#", "method": "width", "width": 1e-12"#)).is_err());
+    }
+
+    #[test]
+    fn freedman_diaconis_is_clamped() {
+        // Tight IQR with one far outlier would ask for billions of bins
+        let mut values: Vec<f64> = (0..100).map(|v| v as f64 * 1e-9).collect();
+        values.push(1e6);
+        let chart = histogram(&values, &config(r#", "method": "freedman-diaconis""#)).unwrap();
+        assert_eq!(counts(&chart).len(), MAX_BINS);
+    }
+
+    #[test]
+    fn density_integrates_to_about_one() {
+        let values = sorted(&[1.0, 2.0, 2.5, 3.0, 7.0]);
+        let density = kernel_density(&values, None, 512);
+        let points = density["points"].as_array().unwrap();
+        let step = points[1]["x"].as_f64().unwrap() - points[0]["x"].as_f64().unwrap();
+        let area: f64 = points.iter().map(|p| p["y"].as_f64().unwrap() * step).sum();
+        assert!((area - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn large_inputs_use_a_binned_density_close_to_the_exact_one() {
+        let values = sorted(&(0..50_000).map(|i| ((i * 7919) % 10_007) as f64 / 100.0).collect::<Vec<_>>());
+        let density = kernel_density(&values, None, 64);
+        assert_eq!(density["method"], "binned");
+        let bandwidth = density["bandwidth"].as_f64().unwrap();
+        let normaliser = values.len() as f64 * bandwidth * (2.0 * std::f64::consts::PI).sqrt();
+        for point in density["points"].as_array().unwrap() {
+            let x = point["x"].as_f64().unwrap();
+            let exact = values.iter().map(|v| (-0.5 * ((x - v) / bandwidth).powi(2)).exp()).sum::<f64>() / normaliser;
+            assert!((point["y"].as_f64().unwrap() - exact).abs() < 1e-3 * exact.max(1e-3));
+        }
+        assert_eq!(kernel_density(&sorted(&[1.0, 2.0]), None, 8)["method"], "exact");
+    }
+
+    #[test]
+    fn boxplot_flags_values_past_the_fences() {
+        let values = [1.0, 2.0, 3.0, 4.0, 100.0];
+        let summary = five_number_summary(&values, 10);
+        assert_eq!(summary["n_outliers"], 1);
+        assert_eq!(summary["upper_whisker"], 4.0);
+    }
+}
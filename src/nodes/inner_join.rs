@@ -1,7 +1,9 @@
-use csv::{ReaderBuilder, WriterBuilder};
+use csv::WriterBuilder;
 use std::collections::HashSet;
 use std::error::Error;
 
+use crate::nodes::dialect::open_reader;
+
 pub struct Inner_Join;
 
 impl Inner_Join {
@@ -14,8 +16,8 @@ impl Inner_Join {
     ) -> Result<String, Box<dyn Error>> {
         // Create CSV readers for both files
         println!("file1: {}, file2: {}", file1, file2);
-        let mut reader1 = ReaderBuilder::new().from_path(file1)?;
-        let mut reader2 = ReaderBuilder::new().from_path(file2)?;
+        let mut reader1 = open_reader(file1)?;
+        let mut reader2 = open_reader(file2)?;
     
         // Get headers for both files and find the index of the `data` column
         let headers1 = reader1.headers()?.clone();
@@ -70,7 +72,7 @@ impl Inner_Join {
             let mut combined_row: Vec<String> = vec!["".to_string(); combined_headers_vec.len()];
     
             // Reset and iterate through reader1 to find the matching row
-            let mut reader1 = ReaderBuilder::new().from_path(file1)?; // Recreate reader1
+            let mut reader1 = open_reader(file1)?; // Recreate reader1
             reader1.headers()?; // Skip headers
             for result in reader1.records() {
                 let record = result?;
@@ -89,7 +91,7 @@ impl Inner_Join {
             }
     
             // Reset and iterate through reader2 to find the matching row
-            let mut reader2 = ReaderBuilder::new().from_path(file2)?; // Recreate reader1
+            let mut reader2 = open_reader(file2)?; // Recreate reader1
             reader2.headers()?; // Skip headers
             for result in reader2.records() {
                 let record = result?;
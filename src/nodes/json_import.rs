@@ -0,0 +1,226 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+use crate::nodes::dialect::{self, Dialect};
+use crate::nodes::table::Table;
+
+const JSON_EXTENSIONS: [&str; 1] = ["json"];
+const NDJSON_EXTENSIONS: [&str; 2] = ["ndjson", "jsonl"];
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrayHandling {
+    #[default]
+    Serialize, // Keep the array as JSON text in one cell
+    Explode, // One row per element, several arrays in a record multiply out
+}
+
+#[derive(Deserialize, Default, Debug)]
+pub struct JsonImportOptions {
+    #[serde(default)]
+    pub arrays: ArrayHandling,
+    #[serde(default)]
+    pub records_path: Option<String>, // Dotted path to the record array inside a wrapper object, e.g. `data.items`
+    #[serde(default)]
+    pub separator: Option<String>, // Joins nested keys into column names, `.` when not set
+}
+
+fn extension(file_path: &str) -> String {
+    Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default()
+}
+
+pub fn is_json(file_path: &str) -> bool {
+    let extension = extension(file_path);
+    JSON_EXTENSIONS.contains(&extension.as_str()) || NDJSON_EXTENSIONS.contains(&extension.as_str())
+}
+
+// Flatten a JSON array or NDJSON file into CSV next to it, in the default dialect
+pub fn convert_to_csv(file_path: &str, options: &JsonImportOptions) -> Result<(String, serde_json::Value), Box<dyn Error>> {
+    let text = std::fs::read_to_string(file_path)?;
+    let records = if NDJSON_EXTENSIONS.contains(&extension(file_path).as_str()) {
+        parse_ndjson(&text)?
+    } else {
+        match serde_json::from_str::<Value>(&text) {
+            Ok(document) => select_records(document, options.records_path.as_deref())?,
+            // Many `.json` dumps are really one object per line
+            Err(err) => parse_ndjson(&text).map_err(|_| err)?,
+        }
+    };
+
+    let separator = options.separator.as_deref().unwrap_or(".");
+    let mut headers: Vec<String> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut flat_rows: Vec<Vec<(String, String)>> = Vec::new();
+    for record in &records {
+        for row in flatten(record, "", separator, options.arrays) {
+            for (column, _) in &row {
+                if !positions.contains_key(column) {
+                    positions.insert(column.clone(), headers.len());
+                    headers.push(column.clone());
+                }
+            }
+            flat_rows.push(row);
+        }
+    }
+
+    let rows: Vec<Vec<String>> = flat_rows
+        .into_iter()
+        .map(|row| {
+            let mut cells = vec![String::new(); headers.len()];
+            for (column, value) in row {
+                cells[positions[&column]] = value;
+            }
+            cells
+        })
+        .collect();
+    let table = Table { headers, rows };
+
+    let output_file = format!("{}.csv", file_path);
+    table.write_to_path(&output_file)?;
+    dialect::save(&output_file, &Dialect::default())?;
+    println!("{} records of {} written to {}", records.len(), file_path, output_file);
+
+    Ok((
+        output_file,
+        json!({
+            "records": records.len(),
+            "rows": table.rows.len(),
+            "columns": table.headers,
+        }),
+    ))
+}
+
+fn parse_ndjson(text: &str) -> Result<Vec<Value>, Box<dyn Error>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| serde_json::from_str(line).map_err(|err| format!("Line {}: {}", number + 1, err).into()))
+        .collect()
+}
+
+// Records are the top-level array, the array at `records_path`, or the document itself
+fn select_records(document: Value, records_path: Option<&str>) -> Result<Vec<Value>, Box<dyn Error>> {
+    let mut selected = document;
+    if let Some(path) = records_path {
+        for key in path.split('.').filter(|k| !k.is_empty()) {
+            selected = match selected {
+                Value::Object(mut object) => object.remove(key),
+                Value::Array(mut array) => key.parse::<usize>().ok().filter(|i| *i < array.len()).map(|i| array.swap_remove(i)),
+                _ => None,
+            }
+            .ok_or_else(|| format!("Records path '{}' not found at '{}'", path, key))?;
+        }
+    }
+    Ok(match selected {
+        Value::Array(records) => records,
+        other => vec![other],
+    })
+}
+
+fn join(prefix: &str, key: &str, separator: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}{}{}", prefix, separator, key)
+    }
+}
+
+// One value into one or more partial rows of (column, cell)
+fn flatten(value: &Value, prefix: &str, separator: &str, arrays: ArrayHandling) -> Vec<Vec<(String, String)>> {
+    let column = if prefix.is_empty() { "value".to_string() } else { prefix.to_string() };
+    match value {
+        Value::Object(object) => {
+            let mut rows: Vec<Vec<(String, String)>> = vec![Vec::new()];
+            for (key, child) in object {
+                let child_rows = flatten(child, &join(prefix, key, separator), separator, arrays);
+                rows = rows
+                    .iter()
+                    .flat_map(|row| {
+                        child_rows.iter().map(move |child_row| {
+                            let mut combined = row.clone();
+                            combined.extend(child_row.iter().cloned());
+                            combined
+                        })
+                    })
+                    .collect();
+            }
+            rows
+        }
+        Value::Array(elements) if arrays == ArrayHandling::Explode => {
+            if elements.is_empty() {
+                return vec![vec![(column, String::new())]];
+            }
+            elements.iter().flat_map(|element| flatten(element, prefix, separator, arrays)).collect()
+        }
+        Value::Array(_) => vec![vec![(column, value.to_string())]],
+        Value::Null => vec![vec![(column, String::new())]],
+        Value::String(text) => vec![vec![(column, text.clone())]],
+        Value::Bool(_) | Value::Number(_) => vec![vec![(column, value.to_string())]],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(name: &str, contents: &str, options: JsonImportOptions) -> Result<Table, Box<dyn Error>> {
+        let directory = std::env::temp_dir().join(format!("istat_tests_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(name).to_string_lossy().into_owned();
+        std::fs::write(&path, contents).unwrap();
+        let (output, _) = convert_to_csv(&path, &options)?;
+        Table::from_path(&output)
+    }
+
+    #[test]
+    fn flattens_nested_objects_and_keeps_arrays_as_json() {
+        let table = convert(
+            "json_nested.json",
+            r#"[{"id": 1, "user": {"name": "ann", "tags": ["a", "b"]}}, {"id": 2, "extra": null}]"#,
+            JsonImportOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(table.headers, vec!["id", "user.name", "user.tags", "extra"]);
+        assert_eq!(table.rows, vec![vec!["1", "ann", r#"["a","b"]"#, ""], vec!["2", "", "", ""]]);
+    }
+
+    #[test]
+    fn explodes_arrays_into_rows() {
+        let options = JsonImportOptions { arrays: ArrayHandling::Explode, separator: Some("_".to_string()), ..Default::default() };
+        let table = convert("json_explode.json", r#"{"id": 1, "a": [1, 2], "b": [{"x": 3}, {"x": 4}], "c": []}"#, options).unwrap();
+        assert_eq!(table.headers, vec!["id", "a", "b_x", "c"]);
+        assert_eq!(table.rows.len(), 4);
+        assert_eq!(table.rows[3], vec!["1", "2", "4", ""]);
+    }
+
+    #[test]
+    fn follows_the_records_path() {
+        let options = JsonImportOptions { records_path: Some("data.pages.1".to_string()), ..Default::default() };
+        let table = convert("json_path.json", r#"{"data": {"pages": [[{"v": 0}], [{"v": 1}, {"v": 2}]]}}"#, options).unwrap();
+        assert_eq!(table.rows, vec![vec!["1"], vec!["2"]]);
+
+        let options = JsonImportOptions { records_path: Some("data.missing".to_string()), ..Default::default() };
+        let error = convert("json_missing_path.json", r#"{"data": {}}"#, options).err().unwrap();
+        assert!(error.to_string().contains("not found at 'missing'"));
+    }
+
+    #[test]
+    fn reads_ndjson_and_reports_the_bad_line() {
+        let table = convert("json_lines.ndjson", "{\"v\": 1}\n\n{\"v\": \"two\", \"w\": true}\n", JsonImportOptions::default()).unwrap();
+        assert_eq!(table.headers, vec!["v", "w"]);
+        assert_eq!(table.rows, vec![vec!["1", ""], vec!["two", "true"]]);
+
+        // A `.json` file holding one object per line falls back to NDJSON
+        assert_eq!(convert("json_lines.json", "{\"v\": 1}\n{\"v\": 2}\n", JsonImportOptions::default()).unwrap().rows.len(), 2);
+
+        let error = convert("json_bad.jsonl", "{\"v\": 1}\n{oops}\n", JsonImportOptions::default()).err().unwrap();
+        assert!(error.to_string().starts_with("Line 2"));
+    }
+}
@@ -0,0 +1,313 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use serde_json::json;
+use std::error::Error;
+
+use crate::nodes::descriptive::{mean, std_dev};
+use crate::nodes::table::Table;
+
+pub struct K_Means;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+enum Initialization {
+    #[serde(rename = "k-means++")]
+    KMeansPlusPlus,
+    Random,
+}
+
+#[derive(Deserialize)]
+struct ElbowConfig {
+    min_k: usize,
+    max_k: usize,
+}
+
+#[derive(Deserialize)]
+struct KMeansConfig {
+    columns: Vec<String>,
+    k: usize,
+    #[serde(default = "default_init")]
+    init: Initialization,
+    #[serde(default = "default_max_iterations")]
+    max_iterations: usize,
+    #[serde(default)]
+    seed: u64,
+    #[serde(default)]
+    standardize: bool, // Scale columns to unit variance before clustering
+    #[serde(default)]
+    elbow: Option<ElbowConfig>,
+    #[serde(default = "default_label_column")]
+    label_column: String,
+}
+
+// Each k on the elbow curve is a full clustering run, so a request may only ask for this many more
+const MAX_ELBOW_RANGE: usize = 50;
+
+fn default_init() -> Initialization {
+    Initialization::KMeansPlusPlus
+}
+
+fn default_max_iterations() -> usize {
+    300
+}
+
+fn default_label_column() -> String {
+    "cluster".to_string()
+}
+
+struct Clustering {
+    centroids: Vec<Vec<f64>>,
+    labels: Vec<usize>,
+    inertia: f64,
+    iterations: usize,
+    converged: bool,
+}
+
+impl K_Means {
+    // Lloyd's algorithm on the complete rows of the chosen columns, seeded so reruns give the same clusters
+    pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<(serde_json::Value, String), Box<dyn Error>> {
+        let config: KMeansConfig = serde_json::from_str(data)?;
+        if config.columns.is_empty() {
+            return Err("At least one column is required for k-means".into());
+        }
+
+        if let Some(elbow) = &config.elbow {
+            check_elbow(elbow)?;
+        }
+
+        let mut table = Table::from_path(file1)?;
+        let indices = config
+            .columns
+            .iter()
+            .map(|c| table.column_index(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (positions, mut points) = table.complete_numeric_rows(&indices);
+        if config.k == 0 || config.k > points.len() {
+            return Err(format!("k must be between 1 and the number of complete rows ({})", points.len()).into());
+        }
+
+        let dimensions = indices.len();
+        let mut scale: Vec<(f64, f64)> = vec![(0.0, 1.0); dimensions];
+        if config.standardize {
+            for (d, (center, spread)) in scale.iter_mut().enumerate() {
+                let column: Vec<f64> = points.iter().map(|p| p[d]).collect();
+                let sd = std_dev(&column);
+                *center = mean(&column);
+                *spread = if sd > 0.0 { sd } else { 1.0 };
+            }
+            for point in points.iter_mut() {
+                for (value, (center, spread)) in point.iter_mut().zip(scale.iter()) {
+                    *value = (*value - center) / spread;
+                }
+            }
+        }
+
+        let result = cluster(&points, config.k, config.init, config.max_iterations, config.seed);
+
+        let elbow: Vec<serde_json::Value> = match &config.elbow {
+            Some(elbow) => (elbow.min_k.max(1)..=elbow.max_k.min(points.len()))
+                .map(|k| {
+                    let inertia = cluster(&points, k, config.init, config.max_iterations, config.seed).inertia;
+                    json!({ "k": k, "inertia": inertia })
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut sizes = vec![0usize; config.k];
+        for label in &result.labels {
+            sizes[*label] += 1;
+        }
+        // Report centroids in the original units of the columns
+        let centroids: Vec<serde_json::Value> = result
+            .centroids
+            .iter()
+            .enumerate()
+            .map(|(cluster, centroid)| {
+                let center: serde_json::Map<String, serde_json::Value> = config
+                    .columns
+                    .iter()
+                    .zip(centroid.iter().zip(scale.iter()))
+                    .map(|(column, (value, (center, spread)))| (column.clone(), json!(value * spread + center)))
+                    .collect();
+                json!({ "cluster": cluster, "size": sizes[cluster], "center": center })
+            })
+            .collect();
+
+        let labels: Vec<String> = result.labels.iter().map(|l| l.to_string()).collect();
+        table.append_columns(&[config.label_column.as_str()], &positions, &[labels]);
+
+        let output_file = format!("./storage_bin/kmeans_{}.csv", node_id);
+        table.write_to_path(&output_file)?;
+        println!("Cluster labels written to {}", output_file);
+
+        Ok((
+            json!({
+                "k": config.k,
+                "n_observations": points.len(),
+                "n_dropped": table.rows.len() - points.len(),
+                "iterations": result.iterations,
+                "converged": result.converged,
+                "inertia": result.inertia,
+                "centroids": centroids,
+                "elbow": elbow,
+            }),
+            output_file,
+        ))
+    }
+}
+
+fn squared_distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+fn nearest(point: &[f64], centroids: &[Vec<f64>]) -> (usize, f64) {
+    centroids
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (i, squared_distance(point, c)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.0))
+}
+
+fn initial_centroids(points: &[Vec<f64>], k: usize, init: Initialization, rng: &mut StdRng) -> Vec<Vec<f64>> {
+    match init {
+        Initialization::Random => {
+            let chosen = rand::seq::index::sample(rng, points.len(), k);
+            chosen.iter().map(|i| points[i].clone()).collect()
+        }
+        Initialization::KMeansPlusPlus => {
+            // Each new centre is drawn with probability proportional to its squared distance from the nearest chosen centre
+            let mut centroids = vec![points[rng.random_range(0..points.len())].clone()];
+            while centroids.len() < k {
+                let distances: Vec<f64> = points.iter().map(|p| nearest(p, &centroids).1).collect();
+                let total: f64 = distances.iter().sum();
+                if total == 0.0 {
+                    centroids.push(points[rng.random_range(0..points.len())].clone());
+                    continue;
+                }
+                let mut target = rng.random::<f64>() * total;
+                let mut chosen = points.len() - 1;
+                for (i, distance) in distances.iter().enumerate() {
+                    target -= distance;
+                    if target <= 0.0 {
+                        chosen = i;
+                        break;
+                    }
+                }
+                centroids.push(points[chosen].clone());
+            }
+            centroids
+        }
+    }
+}
+
+fn cluster(points: &[Vec<f64>], k: usize, init: Initialization, max_iterations: usize, seed: u64) -> Clustering {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut centroids = initial_centroids(points, k, init, &mut rng);
+    let mut labels = vec![usize::MAX; points.len()]; // Unassigned, so the first pass always counts as a change
+    let mut iterations = 0;
+    let mut converged = false;
+
+    while iterations < max_iterations.max(1) {
+        iterations += 1;
+        let mut changed = false;
+        for (point, label) in points.iter().zip(labels.iter_mut()) {
+            let (closest, _) = nearest(point, &centroids);
+            changed |= closest != *label;
+            *label = closest;
+        }
+        if !changed {
+            converged = true;
+            break;
+        }
+
+        let dimensions = points[0].len();
+        let mut sums = vec![vec![0.0; dimensions]; k];
+        let mut counts = vec![0usize; k];
+        for (point, label) in points.iter().zip(labels.iter()) {
+            counts[*label] += 1;
+            for (sum, value) in sums[*label].iter_mut().zip(point.iter()) {
+                *sum += value;
+            }
+        }
+        for cluster in 0..k {
+            if counts[cluster] > 0 {
+                centroids[cluster] = sums[cluster].iter().map(|s| s / counts[cluster] as f64).collect();
+            } else {
+                // Re-seed an empty cluster with the point currently furthest from its centre
+                let furthest = points
+                    .iter()
+                    .zip(labels.iter())
+                    .map(|(p, l)| squared_distance(p, &centroids[*l]))
+                    .enumerate()
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(i, _)| i)
+                    .unwrap_or(0);
+                centroids[cluster] = points[furthest].clone();
+            }
+        }
+    }
+
+    let inertia = points.iter().zip(labels.iter()).map(|(p, l)| squared_distance(p, &centroids[*l])).sum();
+    Clustering { centroids, labels, inertia, iterations, converged }
+}
+
+fn check_elbow(elbow: &ElbowConfig) -> Result<(), Box<dyn Error>> {
+    if elbow.min_k > elbow.max_k {
+        return Err(format!("The elbow min_k ({}) is larger than max_k ({})", elbow.min_k, elbow.max_k).into());
+    }
+    if elbow.max_k - elbow.min_k > MAX_ELBOW_RANGE {
+        return Err(format!("The elbow range may span at most {} values of k past min_k", MAX_ELBOW_RANGE).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blobs() -> Vec<Vec<f64>> {
+        vec![vec![0.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0], vec![10.0, 10.0], vec![10.0, 11.0], vec![11.0, 10.0]]
+    }
+
+    #[test]
+    fn separates_two_blobs_with_known_inertia() {
+        for init in [Initialization::KMeansPlusPlus, Initialization::Random] {
+            let result = cluster(&blobs(), 2, init, 100, 7);
+            assert!(result.converged);
+            assert_eq!(result.labels[0], result.labels[1]);
+            assert_eq!(result.labels[0], result.labels[2]);
+            assert_eq!(result.labels[3], result.labels[5]);
+            assert_ne!(result.labels[0], result.labels[3]);
+            // Each blob contributes 2/9 + 5/9 + 5/9 around its centroid one third of the way in
+            assert!((result.inertia - 8.0 / 3.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_clusters() {
+        let points: Vec<Vec<f64>> = (0..40).map(|i| vec![(i * 7 % 13) as f64, (i * 5 % 11) as f64]).collect();
+        let first = cluster(&points, 4, Initialization::KMeansPlusPlus, 100, 42);
+        let second = cluster(&points, 4, Initialization::KMeansPlusPlus, 100, 42);
+        assert_eq!(first.labels, second.labels);
+        assert_eq!(first.inertia, second.inertia);
+    }
+
+    #[test]
+    fn duplicate_points_still_get_k_centroids() {
+        let points = vec![vec![1.0], vec![1.0], vec![1.0]];
+        let result = cluster(&points, 2, Initialization::KMeansPlusPlus, 10, 0);
+        assert_eq!(result.centroids.len(), 2);
+        assert_eq!(result.inertia, 0.0);
+    }
+
+    #[test]
+    fn elbow_ranges_are_ordered_and_capped() {
+        assert!(check_elbow(&ElbowConfig { min_k: 1, max_k: 1 + MAX_ELBOW_RANGE }).is_ok());
+        assert!(check_elbow(&ElbowConfig { min_k: 1, max_k: 2 + MAX_ELBOW_RANGE }).is_err());
+        let reversed = check_elbow(&ElbowConfig { min_k: 5, max_k: 2 }).unwrap_err();
+        assert!(reversed.to_string().contains("larger than max_k"));
+    }
+}
@@ -0,0 +1,207 @@
+use serde::Deserialize;
+use serde_json::json;
+use statrs::distribution::{ChiSquared, ContinuousCDF, FisherSnedecor, StudentsT};
+use std::error::Error;
+
+use crate::nodes::descriptive::{quantile_sorted, sorted};
+use crate::nodes::design_matrix::DesignMatrix;
+use crate::nodes::table::{parse_number, Table};
+
+pub struct Linear_Regression;
+
+#[derive(Deserialize)]
+struct LinearRegressionConfig {
+    target: String,
+    predictors: Vec<String>,
+    #[serde(default)]
+    categorical: Vec<String>, // Numeric columns that should still be dummy coded
+    #[serde(default = "default_intercept")]
+    intercept: bool,
+    #[serde(default = "default_confidence_level")]
+    confidence_level: f64,
+    #[serde(default)]
+    append_fitted: bool, // Write the input table with `fitted` and `residual` columns for downstream nodes
+}
+
+fn default_intercept() -> bool {
+    true
+}
+
+fn default_confidence_level() -> f64 {
+    0.95
+}
+
+impl Linear_Regression {
+    // Fit an ordinary least squares model, returns the summary and the path of the fitted table if requested
+    pub fn process_node(
+        &self,
+        file1: &str,
+        data: &str,
+        node_id: &u32,
+    ) -> Result<(serde_json::Value, Option<String>), Box<dyn Error>> {
+        let config: LinearRegressionConfig = serde_json::from_str(data)?;
+        if config.confidence_level <= 0.0 || config.confidence_level >= 1.0 {
+            return Err("confidence_level must be between 0 and 1".into());
+        }
+
+        let table = Table::from_path(file1)?;
+        let design = DesignMatrix::build(
+            &table,
+            &config.target,
+            &config.predictors,
+            &config.categorical,
+            config.intercept,
+            parse_number,
+        )?;
+
+        let x = &design.x;
+        let y = &design.y;
+        let n = x.nrows();
+        let p = x.ncols();
+
+        let xtx_inv = (x.transpose() * x)
+            .try_inverse()
+            .ok_or("Predictors are perfectly collinear, the model cannot be estimated")?;
+        let beta = &xtx_inv * x.transpose() * y;
+        let fitted = x * &beta;
+        let residuals = y - &fitted;
+
+        let df_residual = (n - p) as f64;
+        let sse = residuals.norm_squared();
+        let sigma2 = sse / df_residual;
+
+        // Total sum of squares is centred only when the model has an intercept
+        let sst = if config.intercept {
+            let y_mean = y.mean();
+            y.iter().map(|v| (v - y_mean).powi(2)).sum::<f64>()
+        } else {
+            y.norm_squared()
+        };
+        let intercept_terms = if config.intercept { 1 } else { 0 };
+        let df_model = (p - intercept_terms) as f64;
+        let r_squared = 1.0 - sse / sst;
+        let adj_r_squared = 1.0 - (1.0 - r_squared) * (n - intercept_terms) as f64 / df_residual;
+
+        let t_dist = StudentsT::new(0.0, 1.0, df_residual)?;
+        let t_critical = t_dist.inverse_cdf(1.0 - (1.0 - config.confidence_level) / 2.0);
+
+        let mut coefficients: Vec<serde_json::Value> = Vec::new();
+        for (i, term) in design.terms.iter().enumerate() {
+            let estimate = beta[i];
+            let std_error = (sigma2 * xtx_inv[(i, i)]).sqrt();
+            let t_value = estimate / std_error;
+            let p_value = 2.0 * (1.0 - t_dist.cdf(t_value.abs()));
+            coefficients.push(json!({
+                "term": term,
+                "estimate": estimate,
+                "std_error": std_error,
+                "t_value": t_value,
+                "p_value": p_value,
+                "ci_lower": estimate - t_critical * std_error,
+                "ci_upper": estimate + t_critical * std_error,
+            }));
+        }
+
+        let (f_statistic, f_p_value) = if df_model > 0.0 {
+            let f = ((sst - sse) / df_model) / sigma2;
+            let f_dist = FisherSnedecor::new(df_model, df_residual)?;
+            (f, 1.0 - f_dist.cdf(f))
+        } else {
+            (f64::NAN, f64::NAN)
+        };
+
+        let residual_values: Vec<f64> = residuals.iter().copied().collect();
+        let sorted_residuals = sorted(&residual_values);
+
+        // Durbin-Watson for autocorrelation in row order
+        let durbin_watson = residual_values.windows(2).map(|w| (w[1] - w[0]).powi(2)).sum::<f64>() / sse;
+
+        // Jarque-Bera normality test on the residuals
+        let m2 = sse / n as f64;
+        let m3 = residual_values.iter().map(|e| e.powi(3)).sum::<f64>() / n as f64;
+        let m4 = residual_values.iter().map(|e| e.powi(4)).sum::<f64>() / n as f64;
+        let skewness = m3 / m2.powf(1.5);
+        let kurtosis = m4 / m2.powi(2);
+        let jarque_bera = n as f64 / 6.0 * (skewness.powi(2) + (kurtosis - 3.0).powi(2) / 4.0);
+        let jarque_bera_p_value = 1.0 - ChiSquared::new(2.0)?.cdf(jarque_bera);
+
+        let n_dropped = table.rows.len() - n;
+        let output_path = if config.append_fitted {
+            let mut output = table;
+            output.append_columns(
+                &["fitted", "residual"],
+                &design.row_indices,
+                &[
+                    fitted.iter().map(|v| v.to_string()).collect(),
+                    residuals.iter().map(|v| v.to_string()).collect(),
+                ],
+            );
+
+            let output_file = format!("./storage_bin/linear_regression_{}.csv", node_id);
+            output.write_to_path(&output_file)?;
+            println!("Fitted values written to {}", output_file);
+            Some(output_file)
+        } else {
+            None
+        };
+
+        Ok((
+            json!({
+                "target": config.target,
+                "n_observations": n,
+                "n_dropped": n_dropped,
+                "coefficients": coefficients,
+                "categorical_predictors": design.categorical,
+                "confidence_level": config.confidence_level,
+                "r_squared": r_squared,
+                "adj_r_squared": adj_r_squared,
+                "f_statistic": f_statistic,
+                "f_df_model": df_model,
+                "f_df_residual": df_residual,
+                "f_p_value": f_p_value,
+                "residuals": {
+                    "std_error": sigma2.sqrt(),
+                    "min": quantile_sorted(&sorted_residuals, 0.0),
+                    "q1": quantile_sorted(&sorted_residuals, 0.25),
+                    "median": quantile_sorted(&sorted_residuals, 0.5),
+                    "q3": quantile_sorted(&sorted_residuals, 0.75),
+                    "max": quantile_sorted(&sorted_residuals, 1.0),
+                    "durbin_watson": durbin_watson,
+                    "skewness": skewness,
+                    "kurtosis": kurtosis,
+                    "jarque_bera": jarque_bera,
+                    "jarque_bera_p_value": jarque_bera_p_value,
+                },
+            }),
+            output_path,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::write_csv;
+
+    #[test]
+    fn simple_regression_matches_known_answer() {
+        let file = write_csv("linear_regression_known", "x,y\n1,2.1\n2,3.9\n3,6.2\n4,7.8\n5,10.1\n");
+        let (summary, output) = Linear_Regression
+            .process_node(&file, r#"{"target": "y", "predictors": ["x"]}"#, &0)
+            .unwrap();
+
+        assert!(output.is_none());
+        let coefficients = summary["coefficients"].as_array().unwrap();
+        assert!((coefficients[0]["estimate"].as_f64().unwrap() - 0.05).abs() < 1e-9);
+        assert!((coefficients[1]["estimate"].as_f64().unwrap() - 1.99).abs() < 1e-9);
+        assert!((summary["r_squared"].as_f64().unwrap() - (1.0 - 0.107 / 39.708)).abs() < 1e-9);
+        assert_eq!(summary["n_observations"], 5);
+    }
+
+    #[test]
+    fn collinear_predictors_are_reported() {
+        let file = write_csv("linear_regression_collinear", "x,z,y\n1,2,1\n2,4,3\n3,6,2\n4,8,5\n");
+        let result = Linear_Regression.process_node(&file, r#"{"target": "y", "predictors": ["x", "z"]}"#, &0);
+        assert!(result.unwrap_err().to_string().contains("collinear"));
+    }
+}
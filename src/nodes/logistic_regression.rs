@@ -0,0 +1,355 @@
+use nalgebra::{DMatrix, DVector};
+use serde::Deserialize;
+use serde_json::json;
+use statrs::distribution::{ChiSquared, ContinuousCDF, Normal};
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use crate::nodes::descriptive::average_ranks;
+use crate::nodes::design_matrix::DesignMatrix;
+use crate::nodes::table::{is_missing, Table};
+
+pub struct Logistic_Regression;
+
+#[derive(Deserialize)]
+struct LogisticRegressionConfig {
+    target: String,
+    predictors: Vec<String>,
+    #[serde(default)]
+    categorical: Vec<String>, // Numeric columns that should still be dummy coded
+    #[serde(default)]
+    positive_class: Option<String>, // Target value treated as 1, everything else is 0
+    #[serde(default = "default_intercept")]
+    intercept: bool,
+    #[serde(default = "default_confidence_level")]
+    confidence_level: f64,
+    #[serde(default = "default_threshold")]
+    threshold: f64, // Probability cut-off for the confusion matrix
+    #[serde(default = "default_max_iterations")]
+    max_iterations: usize,
+    #[serde(default)]
+    append_fitted: bool, // Write the input table with predicted probabilities for downstream nodes
+}
+
+fn default_intercept() -> bool {
+    true
+}
+
+fn default_confidence_level() -> f64 {
+    0.95
+}
+
+fn default_threshold() -> f64 {
+    0.5
+}
+
+fn default_max_iterations() -> usize {
+    25
+}
+
+const TOLERANCE: f64 = 1e-8;
+const SEPARATION_TOLERANCE: f64 = 1e-8;
+
+impl Logistic_Regression {
+    // Fit a binary logistic model by iteratively reweighted least squares
+    pub fn process_node(
+        &self,
+        file1: &str,
+        data: &str,
+        node_id: &u32,
+    ) -> Result<(serde_json::Value, Option<String>), Box<dyn Error>> {
+        let config: LogisticRegressionConfig = serde_json::from_str(data)?;
+        if config.confidence_level <= 0.0 || config.confidence_level >= 1.0 {
+            return Err("confidence_level must be between 0 and 1".into());
+        }
+        if config.max_iterations == 0 {
+            return Err("max_iterations must be positive".into());
+        }
+
+        let table = Table::from_path(file1)?;
+        let positive_class = match &config.positive_class {
+            Some(class) => class.clone(),
+            None => infer_positive_class(&table, &config.target)?,
+        };
+        let design = DesignMatrix::build(
+            &table,
+            &config.target,
+            &config.predictors,
+            &config.categorical,
+            config.intercept,
+            |value| {
+                if is_missing(value) {
+                    None
+                } else if value.trim() == positive_class {
+                    Some(1.0)
+                } else {
+                    Some(0.0)
+                }
+            },
+        )?;
+
+        let x = &design.x;
+        let y = &design.y;
+        let n = x.nrows();
+        let p = x.ncols();
+        let n_positive = y.iter().filter(|v| **v == 1.0).count();
+        if n_positive == 0 || n_positive == n {
+            return Err(format!(
+                "Target '{}' has a single class among the complete rows, both outcomes are needed",
+                config.target
+            )
+            .into());
+        }
+
+        let mut beta = DVector::<f64>::zeros(p);
+        let mut covariance = DMatrix::<f64>::zeros(p, p);
+        let mut iterations = 0;
+        let mut converged = false;
+
+        while iterations < config.max_iterations {
+            iterations += 1;
+            let mu = probabilities(x, &beta);
+            let weights = mu.map(|m| (m * (1.0 - m)).max(1e-10));
+
+            // Working response z = eta + (y - mu) / w, solved as weighted least squares
+            let eta = x * &beta;
+            let z = DVector::from_iterator(n, (0..n).map(|i| eta[i] + (y[i] - mu[i]) / weights[i]));
+            let xtw = DMatrix::from_fn(p, n, |r, c| x[(c, r)] * weights[c]);
+            covariance = (&xtw * x)
+                .try_inverse()
+                .ok_or("Predictors are perfectly collinear, the model cannot be estimated")?;
+            let next = &covariance * &xtw * &z;
+
+            let change = (&next - &beta).amax();
+            beta = next;
+            if !beta.iter().all(|b| b.is_finite()) {
+                return Err("Estimation diverged, the outcome may be perfectly separated by the predictors".into());
+            }
+            if change < TOLERANCE {
+                converged = true;
+                break;
+            }
+        }
+
+        let mu = probabilities(x, &beta);
+        // Under separation the coefficients keep growing while the fitted probabilities reach 0 or 1
+        let separated = mu.iter().zip(y.iter()).any(|(m, y)| (m - y).abs() < SEPARATION_TOLERANCE);
+        if !converged && separated {
+            return Err("Estimation diverged, the outcome is perfectly separated by the predictors".into());
+        }
+        let log_likelihood = log_likelihood(y, &mu);
+
+        // Null model: intercept only, or a constant 0.5 probability when there is no intercept
+        let y_mean = y.mean();
+        let null_probability = if config.intercept { y_mean } else { 0.5 };
+        let null_log_likelihood = log_likelihood_constant(y, null_probability);
+        let df_model = (p - if config.intercept { 1 } else { 0 }) as f64;
+        let lr_statistic = 2.0 * (log_likelihood - null_log_likelihood);
+        let lr_p_value = if df_model > 0.0 {
+            1.0 - ChiSquared::new(df_model)?.cdf(lr_statistic)
+        } else {
+            f64::NAN
+        };
+
+        let normal = Normal::new(0.0, 1.0)?;
+        let z_critical = normal.inverse_cdf(1.0 - (1.0 - config.confidence_level) / 2.0);
+
+        let mut coefficients: Vec<serde_json::Value> = Vec::new();
+        for (i, term) in design.terms.iter().enumerate() {
+            let estimate = beta[i];
+            let std_error = covariance[(i, i)].sqrt();
+            let z_value = estimate / std_error;
+            let p_value = 2.0 * (1.0 - normal.cdf(z_value.abs()));
+            let ci_lower = estimate - z_critical * std_error;
+            let ci_upper = estimate + z_critical * std_error;
+            coefficients.push(json!({
+                "term": term,
+                "estimate": estimate,
+                "std_error": std_error,
+                "z_value": z_value,
+                "p_value": p_value,
+                "ci_lower": ci_lower,
+                "ci_upper": ci_upper,
+                "odds_ratio": estimate.exp(),
+                "odds_ratio_ci_lower": ci_lower.exp(),
+                "odds_ratio_ci_upper": ci_upper.exp(),
+            }));
+        }
+
+        let (mut tp, mut fp, mut tn, mut fn_) = (0usize, 0usize, 0usize, 0usize);
+        for i in 0..n {
+            let predicted = mu[i] >= config.threshold;
+            let actual = y[i] == 1.0;
+            match (predicted, actual) {
+                (true, true) => tp += 1,
+                (true, false) => fp += 1,
+                (false, false) => tn += 1,
+                (false, true) => fn_ += 1,
+            }
+        }
+
+        let n_dropped = table.rows.len() - n;
+        let output_path = if config.append_fitted {
+            let mut output = table;
+            output.append_columns(
+                &["predicted_probability", "predicted_class"],
+                &design.row_indices,
+                &[
+                    mu.iter().map(|v| v.to_string()).collect(),
+                    mu.iter().map(|v| if *v >= config.threshold { "1" } else { "0" }.to_string()).collect(),
+                ],
+            );
+
+            let output_file = format!("./storage_bin/logistic_regression_{}.csv", node_id);
+            output.write_to_path(&output_file)?;
+            println!("Predicted probabilities written to {}", output_file);
+            Some(output_file)
+        } else {
+            None
+        };
+
+        Ok((
+            json!({
+                "target": config.target,
+                "positive_class": positive_class,
+                "n_observations": n,
+                "n_dropped": n_dropped,
+                "n_positive": n_positive,
+                "converged": converged,
+                "iterations": iterations,
+                "coefficients": coefficients,
+                "categorical_predictors": design.categorical,
+                "confidence_level": config.confidence_level,
+                "log_likelihood": log_likelihood,
+                "null_log_likelihood": null_log_likelihood,
+                "aic": -2.0 * log_likelihood + 2.0 * p as f64,
+                "bic": -2.0 * log_likelihood + (n as f64).ln() * p as f64,
+                "pseudo_r_squared": 1.0 - log_likelihood / null_log_likelihood, // McFadden
+                "lr_statistic": lr_statistic,
+                "lr_df": df_model,
+                "lr_p_value": lr_p_value,
+                "confusion_matrix": {
+                    "threshold": config.threshold,
+                    "true_positive": tp,
+                    "false_positive": fp,
+                    "true_negative": tn,
+                    "false_negative": fn_,
+                    "accuracy": (tp + tn) as f64 / n as f64,
+                    "precision": tp as f64 / (tp + fp) as f64,
+                    "recall": tp as f64 / (tp + fn_) as f64,
+                    "specificity": tn as f64 / (tn + fp) as f64,
+                },
+                "roc_auc": roc_auc(y, &mu),
+            }),
+            output_path,
+        ))
+    }
+}
+
+// Use 1/true/yes when the target looks boolean, otherwise the greater of exactly two levels
+fn infer_positive_class(table: &Table, target: &str) -> Result<String, Box<dyn Error>> {
+    let index = table.column_index(target)?;
+    let levels: BTreeSet<String> = table
+        .rows
+        .iter()
+        .filter_map(|row| row.get(index))
+        .filter(|v| !is_missing(v))
+        .map(|v| v.trim().to_string())
+        .collect();
+
+    if levels.len() > 2 {
+        return Err(format!(
+            "Target '{}' has {} distinct values, set positive_class to choose the outcome",
+            target,
+            levels.len()
+        )
+        .into());
+    }
+    for candidate in ["1", "true", "TRUE", "True", "yes", "Yes", "YES"] {
+        if levels.contains(candidate) {
+            return Ok(candidate.to_string());
+        }
+    }
+    levels
+        .into_iter()
+        .next_back()
+        .ok_or_else(|| format!("Target '{}' has no values", target).into())
+}
+
+fn probabilities(x: &DMatrix<f64>, beta: &DVector<f64>) -> DVector<f64> {
+    (x * beta).map(|eta| 1.0 / (1.0 + (-eta).exp()))
+}
+
+fn log_likelihood(y: &DVector<f64>, mu: &DVector<f64>) -> f64 {
+    y.iter()
+        .zip(mu.iter())
+        .map(|(y, m)| {
+            let m = m.clamp(1e-15, 1.0 - 1e-15);
+            y * m.ln() + (1.0 - y) * (1.0 - m).ln()
+        })
+        .sum()
+}
+
+fn log_likelihood_constant(y: &DVector<f64>, probability: f64) -> f64 {
+    log_likelihood(y, &DVector::from_element(y.len(), probability))
+}
+
+// Area under the ROC curve via the Mann-Whitney rank statistic
+fn roc_auc(y: &DVector<f64>, mu: &DVector<f64>) -> f64 {
+    let ranks = average_ranks(mu.as_slice());
+    let n_positive = y.iter().filter(|v| **v == 1.0).count() as f64;
+    let n_negative = y.len() as f64 - n_positive;
+    let positive_rank_sum: f64 = (0..y.len()).filter(|i| y[*i] == 1.0).map(|i| ranks[i]).sum();
+    (positive_rank_sum - n_positive * (n_positive + 1.0) / 2.0) / (n_positive * n_negative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::write_csv;
+
+    #[test]
+    fn binary_predictor_matches_closed_form() {
+        // With one binary predictor the MLE is the log odds of each group
+        let file = write_csv("logistic_known", "x,y\n0,1\n0,0\n0,0\n0,0\n1,1\n1,1\n1,1\n1,0\n");
+        let (summary, _) = Logistic_Regression
+            .process_node(&file, r#"{"target": "y", "predictors": ["x"]}"#, &0)
+            .unwrap();
+
+        let coefficients = summary["coefficients"].as_array().unwrap();
+        assert!((coefficients[0]["estimate"].as_f64().unwrap() - (1.0f64 / 3.0).ln()).abs() < 1e-6);
+        assert!((coefficients[1]["estimate"].as_f64().unwrap() - 9.0f64.ln()).abs() < 1e-6);
+        assert_eq!(summary["converged"], true);
+        assert_eq!(summary["n_positive"], 4);
+    }
+
+    #[test]
+    fn single_class_is_rejected() {
+        let file = write_csv("logistic_single_class", "x,y\n1,1\n2,1\n3,1\n4,1\n5,NA\n");
+        let result = Logistic_Regression.process_node(&file, r#"{"target": "y", "predictors": ["x"]}"#, &0);
+        assert!(result.unwrap_err().to_string().contains("single class"));
+    }
+
+    #[test]
+    fn perfect_separation_is_rejected() {
+        let file = write_csv("logistic_separated", "x,y\n1,0\n2,0\n3,0\n4,1\n5,1\n6,1\n");
+        let result = Logistic_Regression.process_node(&file, r#"{"target": "y", "predictors": ["x"]}"#, &0);
+        assert!(result.unwrap_err().to_string().contains("separated"));
+    }
+
+    #[test]
+    fn zero_iterations_are_rejected() {
+        let file = write_csv("logistic_zero_iterations", "x,y\n0,1\n0,0\n1,1\n1,0\n");
+        let result = Logistic_Regression.process_node(&file, r#"{"target": "y", "predictors": ["x"], "max_iterations": 0}"#, &0);
+        assert!(result.unwrap_err().to_string().contains("max_iterations"));
+    }
+
+    #[test]
+    fn positive_class_prefers_boolean_looking_values() {
+        let table = Table {
+            headers: vec!["y".to_string()],
+            rows: vec![vec!["no".to_string()], vec!["yes".to_string()]],
+        };
+        assert_eq!(infer_positive_class(&table, "y").unwrap(), "yes");
+    }
+}
@@ -1,3 +1,34 @@
 pub mod inner_join;
 pub mod clean_na;
-pub mod output_csv;
\ No newline at end of file
+pub mod output_csv;
+pub mod table;
+pub mod dialect;
+pub mod descriptive;
+pub mod design_matrix;
+pub mod linear_regression;
+pub mod logistic_regression;
+pub mod correlation;
+pub mod histogram;
+pub mod outliers;
+pub mod transform;
+pub mod datetime;
+pub mod time_series;
+pub mod window_functions;
+pub mod sql_query;
+pub mod runtime;
+pub mod kmeans;
+pub mod pca;
+pub mod survival;
+pub mod resampling;
+pub mod sample;
+pub mod text_transform;
+pub mod cast_columns;
+pub mod spreadsheet;
+pub mod columnar;
+pub mod output_parquet;
+pub mod json_import;
+pub mod db_source;
+pub mod export;
+pub mod preview;
+pub mod compressed;
+pub mod chunked_upload;
\ No newline at end of file
@@ -0,0 +1,251 @@
+use serde::Deserialize;
+use serde_json::json;
+use statrs::distribution::{ContinuousCDF, StudentsT};
+use std::error::Error;
+
+use crate::nodes::descriptive::{mean, median, quantile_sorted, sorted, std_dev};
+use crate::nodes::table::Table;
+
+pub struct Outlier_Detection;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+enum OutlierMethod {
+    Iqr,
+    ZScore,
+    ModifiedZScore,
+    Grubbs,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum OutlierMode {
+    Flag,
+    Remove,
+}
+
+#[derive(Deserialize)]
+struct OutlierConfig {
+    columns: Vec<String>,
+    #[serde(default = "default_method")]
+    method: OutlierMethod,
+    #[serde(default = "default_mode")]
+    mode: OutlierMode,
+    // IQR multiplier, z cut-off or Grubbs significance level depending on the method
+    #[serde(default)]
+    threshold: Option<f64>,
+}
+
+fn default_method() -> OutlierMethod {
+    OutlierMethod::Iqr
+}
+
+fn default_mode() -> OutlierMode {
+    OutlierMode::Flag
+}
+
+impl Outlier_Detection {
+    // Flag outliers with a `<column>_outlier` column, or drop every row that is an outlier in any column
+    pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<(serde_json::Value, String), Box<dyn Error>> {
+        let config: OutlierConfig = serde_json::from_str(data)?;
+        let threshold = config.threshold.unwrap_or(match config.method {
+            OutlierMethod::Iqr => 1.5,
+            OutlierMethod::ZScore => 3.0,
+            OutlierMethod::ModifiedZScore => 3.5,
+            OutlierMethod::Grubbs => 0.05,
+        });
+        if threshold <= 0.0 {
+            return Err("threshold must be positive".into());
+        }
+
+        let mut table = Table::from_path(file1)?;
+        let rows_in = table.rows.len();
+        let mut flags_per_column: Vec<Vec<bool>> = Vec::new();
+        let mut report: Vec<serde_json::Value> = Vec::new();
+
+        for column in &config.columns {
+            let values = table.numeric_column(table.column_index(column)?);
+            let present: Vec<f64> = values.iter().flatten().copied().collect();
+            let (flags, bounds) = match config.method {
+                OutlierMethod::Iqr => iqr_flags(&values, &present, threshold),
+                OutlierMethod::ZScore => z_score_flags(&values, &present, threshold),
+                OutlierMethod::ModifiedZScore => modified_z_score_flags(&values, &present, threshold),
+                OutlierMethod::Grubbs => grubbs_flags(&values, threshold)?,
+            };
+
+            let n_outliers = flags.iter().filter(|f| **f).count();
+            let mut entry = json!({
+                "column": column,
+                "n": present.len(),
+                "n_outliers": n_outliers,
+            });
+            if let Some((lower, upper)) = bounds {
+                entry["lower_bound"] = json!(lower);
+                entry["upper_bound"] = json!(upper);
+            }
+            report.push(entry);
+            flags_per_column.push(flags);
+        }
+
+        match config.mode {
+            OutlierMode::Flag => {
+                let all_rows: Vec<usize> = (0..rows_in).collect();
+                let names: Vec<String> = config.columns.iter().map(|c| format!("{}_outlier", c)).collect();
+                let name_refs: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+                let columns: Vec<Vec<String>> = flags_per_column
+                    .iter()
+                    .map(|flags| flags.iter().map(|f| f.to_string()).collect())
+                    .collect();
+                table.append_columns(&name_refs, &all_rows, &columns);
+            }
+            OutlierMode::Remove => {
+                let mut row_index = 0;
+                table.rows.retain(|_| {
+                    let keep = !flags_per_column.iter().any(|flags| flags[row_index]);
+                    row_index += 1;
+                    keep
+                });
+            }
+        }
+
+        let output_file = format!("./storage_bin/outliers_{}.csv", node_id);
+        table.write_to_path(&output_file)?;
+        println!("Outlier output written to {}", output_file);
+
+        Ok((
+            json!({
+                "method": match config.method {
+                    OutlierMethod::Iqr => "iqr",
+                    OutlierMethod::ZScore => "z-score",
+                    OutlierMethod::ModifiedZScore => "modified-z-score",
+                    OutlierMethod::Grubbs => "grubbs",
+                },
+                "mode": if config.mode == OutlierMode::Flag { "flag" } else { "remove" },
+                "threshold": threshold,
+                "columns": report,
+                "rows_in": rows_in,
+                "rows_out": table.rows.len(),
+            }),
+            output_file,
+        ))
+    }
+}
+
+type Flags = (Vec<bool>, Option<(f64, f64)>);
+
+fn flag_outside(values: &[Option<f64>], lower: f64, upper: f64) -> Flags {
+    let flags = values
+        .iter()
+        .map(|v| matches!(v, Some(v) if *v < lower || *v > upper))
+        .collect();
+    (flags, Some((lower, upper)))
+}
+
+// Tukey fences at `multiplier` times the interquartile range
+fn iqr_flags(values: &[Option<f64>], present: &[f64], multiplier: f64) -> Flags {
+    let sorted_values = sorted(present);
+    let q1 = quantile_sorted(&sorted_values, 0.25);
+    let q3 = quantile_sorted(&sorted_values, 0.75);
+    let iqr = q3 - q1;
+    flag_outside(values, q1 - multiplier * iqr, q3 + multiplier * iqr)
+}
+
+fn z_score_flags(values: &[Option<f64>], present: &[f64], cutoff: f64) -> Flags {
+    let m = mean(present);
+    let sd = std_dev(present);
+    if sd.is_nan() || sd == 0.0 {
+        return (vec![false; values.len()], None);
+    }
+    flag_outside(values, m - cutoff * sd, m + cutoff * sd)
+}
+
+// Iglewicz and Hoaglin: 0.6745 * (x - median) / MAD
+fn modified_z_score_flags(values: &[Option<f64>], present: &[f64], cutoff: f64) -> Flags {
+    let med = median(present);
+    let deviations: Vec<f64> = present.iter().map(|v| (v - med).abs()).collect();
+    let mad = median(&deviations);
+    if mad.is_nan() || mad == 0.0 {
+        return (vec![false; values.len()], None);
+    }
+    let spread = cutoff * mad / 0.6745;
+    flag_outside(values, med - spread, med + spread)
+}
+
+// Two-sided Grubbs test applied repeatedly, removing the most extreme value while it is significant
+fn grubbs_flags(values: &[Option<f64>], alpha: f64) -> Result<Flags, Box<dyn Error>> {
+    if alpha >= 1.0 {
+        return Err("Grubbs significance level must be below 1".into());
+    }
+    let mut flags = vec![false; values.len()];
+    let mut remaining: Vec<(usize, f64)> = values
+        .iter()
+        .enumerate()
+        .filter_map(|(i, v)| v.map(|v| (i, v)))
+        .collect();
+
+    while remaining.len() > 2 {
+        let present: Vec<f64> = remaining.iter().map(|(_, v)| *v).collect();
+        let m = mean(&present);
+        let sd = std_dev(&present);
+        if sd.is_nan() || sd == 0.0 {
+            break;
+        }
+        let (position, g) = present
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i, (v - m).abs() / sd))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+
+        let n = present.len() as f64;
+        let t = StudentsT::new(0.0, 1.0, n - 2.0)?.inverse_cdf(1.0 - alpha / (2.0 * n));
+        let critical = (n - 1.0) / n.sqrt() * (t * t / (n - 2.0 + t * t)).sqrt();
+        if g <= critical {
+            break;
+        }
+        flags[remaining[position].0] = true;
+        remaining.remove(position);
+    }
+
+    Ok((flags, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(values: &[f64]) -> Vec<Option<f64>> {
+        values.iter().map(|v| Some(*v)).collect()
+    }
+
+    #[test]
+    fn iqr_fences() {
+        let present = [1.0, 2.0, 3.0, 4.0, 5.0, 50.0];
+        let (flags, bounds) = iqr_flags(&column(&present), &present, 1.5);
+        // q1 = 2.25, q3 = 4.75 with linear interpolation
+        assert_eq!(bounds, Some((2.25 - 3.75, 4.75 + 3.75)));
+        assert_eq!(flags, vec![false, false, false, false, false, true]);
+    }
+
+    #[test]
+    fn missing_cells_are_never_flagged() {
+        let values = vec![Some(1.0), None, Some(1.0), Some(100.0)];
+        let (flags, _) = z_score_flags(&values, &[1.0, 1.0, 100.0], 1.0);
+        assert_eq!(flags, vec![false, false, false, true]);
+    }
+
+    #[test]
+    fn constant_column_has_no_z_score_outliers() {
+        let present = [3.0, 3.0, 3.0];
+        let (flags, bounds) = modified_z_score_flags(&column(&present), &present, 3.5);
+        assert!(bounds.is_none());
+        assert!(flags.iter().all(|f| !f));
+    }
+
+    #[test]
+    fn grubbs_removes_only_the_significant_extreme() {
+        let present = [2.1, 2.3, 2.2, 2.4, 2.2, 2.3, 9.0];
+        let (flags, _) = grubbs_flags(&column(&present), 0.05).unwrap();
+        assert_eq!(flags, vec![false, false, false, false, false, false, true]);
+    }
+}
@@ -1,16 +1,14 @@
-use std::fs::File;
-use std::io::{BufReader};
 use serde_json::json;
-use csv::ReaderBuilder;
 use std::error::Error;
 
+use crate::nodes::dialect::open_reader;
+
 pub struct Output_CSV;
 
 impl Output_CSV {
-    // Open CSV file and package its contents as JSON
+    // Open CSV file in its stored dialect and package its contents as JSON
     pub fn process_node(&self, file_path: &str) -> Result<serde_json::Value, Box<dyn Error>> {
-        let file = File::open(file_path)?;
-        let mut reader = ReaderBuilder::new().from_reader(BufReader::new(file));
+        let mut reader = open_reader(file_path)?;
 
         let headers = reader.headers()?.clone(); // Get headers
         let mut csv_data: Vec<serde_json::Value> = Vec::new();
@@ -0,0 +1,261 @@
+use arrow::array::{ArrayRef, BooleanArray, Date32Array, Float64Array, Int64Array, StringArray, TimestampMillisecondArray};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use chrono::{NaiveDate, NaiveDateTime};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::sync::Arc;
+
+use crate::nodes::datetime::{DATE_OUTPUT_FORMAT, OUTPUT_FORMAT};
+use crate::nodes::table::{is_missing, Table};
+
+pub struct Output_Parquet;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum ColumnType {
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    Datetime,
+    String,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum ParquetCompression {
+    None,
+    Snappy,
+    Gzip,
+    Zstd,
+}
+
+#[derive(Deserialize, Default)]
+struct OutputParquetConfig {
+    #[serde(default)]
+    compression: Option<ParquetCompression>, // Snappy when not set
+    #[serde(default)]
+    column_types: HashMap<String, ColumnType>, // Overrides the inferred type of a column
+}
+
+const BATCH_ROWS: usize = 64 * 1024;
+
+impl Output_Parquet {
+    // Write the input table to Parquet with a type per column inferred from its cells
+    pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<(serde_json::Value, String), Box<dyn Error>> {
+        // Accept an empty `data` string, the node has no required settings
+        let config: OutputParquetConfig = if data.trim().is_empty() { OutputParquetConfig::default() } else { serde_json::from_str(data)? };
+        let table = Table::from_path(file1)?;
+        let output_file = format!("./storage_bin/parquet_{}.parquet", node_id);
+        let summary = write_parquet(&table, &config, &output_file)?;
+        println!("Parquet file written to {}", output_file);
+        Ok((summary, output_file))
+    }
+}
+
+// Any table as Parquet with inferred types and default compression, used by downloads
+pub fn export_parquet(file1: &str, output_file: &str) -> Result<(), Box<dyn Error>> {
+    write_parquet(&Table::from_path(file1)?, &OutputParquetConfig::default(), output_file)?;
+    Ok(())
+}
+
+fn write_parquet(table: &Table, config: &OutputParquetConfig, output_file: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+    let types: Vec<ColumnType> = table
+        .headers
+        .iter()
+        .enumerate()
+        .map(|(index, name)| config.column_types.get(name).copied().unwrap_or_else(|| infer_type(table, index)))
+        .collect();
+    let schema = Arc::new(Schema::new(
+        table
+            .headers
+            .iter()
+            .zip(types.iter())
+            .map(|(name, column_type)| Field::new(name, arrow_type(*column_type), true))
+            .collect::<Vec<_>>(),
+    ));
+
+    let compression = match config.compression.unwrap_or(ParquetCompression::Snappy) {
+        ParquetCompression::None => Compression::UNCOMPRESSED,
+        ParquetCompression::Snappy => Compression::SNAPPY,
+        ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+        ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+    };
+    let properties = WriterProperties::builder().set_compression(compression).build();
+
+    let mut writer = ArrowWriter::try_new(File::create(output_file)?, schema.clone(), Some(properties))?;
+    let mut failed = vec![0usize; types.len()];
+    for chunk in table.rows.chunks(BATCH_ROWS) {
+        let columns: Vec<ArrayRef> = types
+            .iter()
+            .enumerate()
+            .map(|(index, column_type)| {
+                let cells: Vec<Option<&str>> = chunk
+                    .iter()
+                    .map(|row| row.get(index).map(|v| v.as_str()).filter(|v| !is_missing(v)))
+                    .collect();
+                let (array, unparsed) = build_array(&cells, *column_type);
+                failed[index] += unparsed;
+                array
+            })
+            .collect();
+        writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+    }
+    writer.close()?;
+
+    let columns: Vec<serde_json::Value> = table
+        .headers
+        .iter()
+        .zip(types.iter().zip(failed.iter()))
+        .map(|(name, (column_type, failed))| json!({ "name": name, "type": format!("{:?}", column_type).to_lowercase(), "nulled": failed }))
+        .collect();
+    Ok(json!({ "rows": table.rows.len(), "columns": columns, "file": output_file }))
+}
+
+fn arrow_type(column_type: ColumnType) -> DataType {
+    match column_type {
+        ColumnType::Integer => DataType::Int64,
+        ColumnType::Float => DataType::Float64,
+        ColumnType::Boolean => DataType::Boolean,
+        ColumnType::Date => DataType::Date32,
+        ColumnType::Datetime => DataType::Timestamp(TimeUnit::Millisecond, None),
+        ColumnType::String => DataType::Utf8,
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), DATE_OUTPUT_FORMAT).ok()
+}
+
+fn parse_datetime(value: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(value.trim(), OUTPUT_FORMAT).ok()
+}
+
+// The narrowest type every non-missing cell parses as, in the canonical forms the other nodes write
+fn infer_type(table: &Table, index: usize) -> ColumnType {
+    let values: Vec<&str> = table.rows.iter().filter_map(|row| row.get(index)).map(|v| v.as_str()).collect();
+    infer_values(&values)
+}
+
+// Name of the inferred type of some cells of a column, for schemas shown to the user
+pub fn infer_type_name(values: &[&str]) -> String {
+    format!("{:?}", infer_values(values)).to_lowercase()
+}
+
+fn infer_values(values: &[&str]) -> ColumnType {
+    let values: Vec<&str> = values.iter().copied().filter(|v| !is_missing(v)).collect();
+    if values.is_empty() {
+        return ColumnType::String;
+    }
+    let all = |parses: fn(&str) -> bool| values.iter().all(|v| parses(v));
+    if all(|v| v.trim().parse::<i64>().is_ok()) {
+        ColumnType::Integer
+    } else if all(|v| v.trim().parse::<f64>().is_ok()) {
+        ColumnType::Float
+    } else if all(|v| parse_bool(v).is_some()) {
+        ColumnType::Boolean
+    } else if all(|v| parse_date(v).is_some()) {
+        ColumnType::Date
+    } else if all(|v| parse_datetime(v).is_some()) {
+        ColumnType::Datetime
+    } else {
+        ColumnType::String
+    }
+}
+
+fn parse_cells<T>(cells: &[Option<&str>], parse: impl Fn(&str) -> Option<T>) -> (Vec<Option<T>>, usize) {
+    let values: Vec<Option<T>> = cells.iter().map(|c| c.and_then(&parse)).collect();
+    let unparsed = cells.iter().zip(values.iter()).filter(|(c, v)| c.is_some() && v.is_none()).count();
+    (values, unparsed)
+}
+
+// Cells that do not parse as the column type (possible with overrides) are written as null and counted
+fn build_array(cells: &[Option<&str>], column_type: ColumnType) -> (ArrayRef, usize) {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap_or_default();
+    match column_type {
+        ColumnType::Integer => {
+            let (values, unparsed) = parse_cells(cells, |v| v.trim().parse::<i64>().ok());
+            (Arc::new(Int64Array::from(values)), unparsed)
+        }
+        ColumnType::Float => {
+            let (values, unparsed) = parse_cells(cells, |v| v.trim().parse::<f64>().ok());
+            (Arc::new(Float64Array::from(values)), unparsed)
+        }
+        ColumnType::Boolean => {
+            let (values, unparsed) = parse_cells(cells, parse_bool);
+            (Arc::new(BooleanArray::from(values)), unparsed)
+        }
+        ColumnType::Date => {
+            let (values, unparsed) = parse_cells(cells, |v| parse_date(v).map(|d| (d - epoch).num_days() as i32));
+            (Arc::new(Date32Array::from(values)), unparsed)
+        }
+        ColumnType::Datetime => {
+            let (values, unparsed) = parse_cells(cells, |v| parse_datetime(v).map(|d| d.and_utc().timestamp_millis()));
+            (Arc::new(TimestampMillisecondArray::from(values)), unparsed)
+        }
+        ColumnType::String => (Arc::new(StringArray::from(cells.to_vec())), 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::columnar;
+    use crate::nodes::table::test_support::{storage_bin, write_csv};
+
+    #[test]
+    fn infers_the_narrowest_type() {
+        assert_eq!(infer_values(&["1", " -2 ", "NA", ""]), ColumnType::Integer);
+        assert_eq!(infer_values(&["1", "2.5"]), ColumnType::Float);
+        assert_eq!(infer_values(&["TRUE", "false"]), ColumnType::Boolean);
+        assert_eq!(infer_values(&["2024-02-29", "2023-01-01"]), ColumnType::Date);
+        assert_eq!(infer_values(&["2024-02-29 10:00:00", "2024-02-29"]), ColumnType::String);
+        assert_eq!(infer_values(&["NA"]), ColumnType::String);
+        assert_eq!(infer_type_name(&["2024-02-29 10:00:00"]), "datetime");
+    }
+
+    #[test]
+    fn round_trips_through_the_columnar_import() {
+        storage_bin();
+        let file = write_csv(
+            "parquet_round_trip",
+            "id,score,ok,day,at,name\n9007199254740993,1.5,true,2024-02-29,2024-02-29 10:30:00,a\n2,,false,2024-03-01,2024-03-01 00:00:00,\n",
+        );
+        let (summary, path) = Output_Parquet.process_node(&file, r#"{"compression": "zstd"}"#, &900_080).unwrap();
+        let types: Vec<&str> = summary["columns"].as_array().unwrap().iter().map(|c| c["type"].as_str().unwrap()).collect();
+        assert_eq!(types, vec!["integer", "float", "boolean", "date", "datetime", "string"]);
+
+        let (csv_path, info) = columnar::convert_to_csv(&path).unwrap();
+        assert_eq!(info["format"], "parquet");
+        assert_eq!(info["rows"], 2);
+        let table = Table::from_path(&csv_path).unwrap();
+        assert_eq!(table.headers, vec!["id", "score", "ok", "day", "at", "name"]);
+        assert_eq!(table.rows[0], vec!["9007199254740993", "1.5", "true", "2024-02-29", "2024-02-29 10:30:00", "a"]);
+        assert_eq!(table.rows[1], vec!["2", "", "false", "2024-03-01", "2024-03-01 00:00:00", ""]);
+    }
+
+    #[test]
+    fn overridden_types_null_cells_that_do_not_parse() {
+        storage_bin();
+        let file = write_csv("parquet_override", "code\n12\nx7\n");
+        let (summary, _) = Output_Parquet.process_node(&file, r#"{"column_types": {"code": "integer"}}"#, &900_081).unwrap();
+        assert_eq!(summary["columns"][0]["nulled"], 1);
+        let (summary, _) = Output_Parquet.process_node(&file, "", &900_082).unwrap();
+        assert_eq!(summary["columns"][0]["type"], "string");
+    }
+}
@@ -0,0 +1,174 @@
+use nalgebra::{DMatrix, SymmetricEigen};
+use serde::Deserialize;
+use serde_json::json;
+use std::error::Error;
+
+use crate::nodes::descriptive::{mean, std_dev};
+use crate::nodes::table::Table;
+
+pub struct Principal_Components;
+
+#[derive(Deserialize)]
+struct PcaConfig {
+    columns: Vec<String>,
+    #[serde(default)]
+    n_components: Option<usize>, // Number of score columns to append, all components when not set
+    #[serde(default = "default_standardize")]
+    standardize: bool, // Work on the correlation matrix rather than the covariance matrix
+    #[serde(default = "default_prefix")]
+    prefix: String,
+}
+
+fn default_standardize() -> bool {
+    true
+}
+
+fn default_prefix() -> String {
+    "PC".to_string()
+}
+
+impl Principal_Components {
+    // Eigen-decomposition of the covariance (or correlation) matrix of the complete rows
+    pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<(serde_json::Value, String), Box<dyn Error>> {
+        let config: PcaConfig = serde_json::from_str(data)?;
+        if config.columns.len() < 2 {
+            return Err("PCA needs at least two columns".into());
+        }
+
+        let mut table = Table::from_path(file1)?;
+        let indices = config
+            .columns
+            .iter()
+            .map(|c| table.column_index(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (positions, rows) = table.complete_numeric_rows(&indices);
+        let n = rows.len();
+        let p = indices.len();
+        if n < 2 {
+            return Err("PCA needs at least two complete rows".into());
+        }
+        let n_components = config.n_components.unwrap_or(p).clamp(1, p);
+
+        // Centre, and scale to unit variance when standardizing
+        let mut centred = DMatrix::from_fn(n, p, |r, c| rows[r][c]);
+        for c in 0..p {
+            let column: Vec<f64> = centred.column(c).iter().copied().collect();
+            let center = mean(&column);
+            let spread = if config.standardize { std_dev(&column) } else { 1.0 };
+            if config.standardize && spread == 0.0 {
+                return Err(format!("Column '{}' is constant and cannot be standardized", config.columns[c]).into());
+            }
+            for value in centred.column_mut(c).iter_mut() {
+                *value = (*value - center) / spread;
+            }
+        }
+
+        let covariance = centred.transpose() * &centred / (n - 1) as f64;
+        let eigen = SymmetricEigen::new(covariance);
+
+        let mut order: Vec<usize> = (0..p).collect();
+        order.sort_by(|a, b| eigen.eigenvalues[*b].total_cmp(&eigen.eigenvalues[*a]));
+        let eigenvalues: Vec<f64> = order.iter().map(|i| eigen.eigenvalues[*i].max(0.0)).collect();
+        let total_variance: f64 = eigenvalues.iter().sum();
+
+        // Flip each component so its largest loading is positive, eigenvector signs are otherwise arbitrary
+        let mut vectors = DMatrix::<f64>::zeros(p, p);
+        for (component, source) in order.iter().enumerate() {
+            let mut vector = eigen.eigenvectors.column(*source).clone_owned();
+            let largest = vector.iter().copied().max_by(|a, b| a.abs().total_cmp(&b.abs())).unwrap_or(0.0);
+            if largest < 0.0 {
+                vector = -vector;
+            }
+            vectors.set_column(component, &vector);
+        }
+
+        let scores = &centred * vectors.columns(0, n_components);
+        let names: Vec<String> = (1..=n_components).map(|i| format!("{}{}", config.prefix, i)).collect();
+        let name_refs: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+        let score_columns: Vec<Vec<String>> = (0..n_components)
+            .map(|c| scores.column(c).iter().map(|v| v.to_string()).collect())
+            .collect();
+        table.append_columns(&name_refs, &positions, &score_columns);
+
+        let output_file = format!("./storage_bin/pca_{}.csv", node_id);
+        table.write_to_path(&output_file)?;
+        println!("Component scores written to {}", output_file);
+
+        let mut cumulative = 0.0;
+        let components: Vec<serde_json::Value> = (0..p)
+            .map(|c| {
+                let ratio = eigenvalues[c] / total_variance;
+                cumulative += ratio;
+                let loadings: serde_json::Map<String, serde_json::Value> = config
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(r, column)| (column.clone(), json!(vectors[(r, c)])))
+                    .collect();
+                json!({
+                    "component": format!("{}{}", config.prefix, c + 1),
+                    "explained_variance": eigenvalues[c],
+                    "explained_variance_ratio": ratio,
+                    "cumulative_variance_ratio": cumulative,
+                    "loadings": loadings,
+                })
+            })
+            .collect();
+
+        Ok((
+            json!({
+                "n_observations": n,
+                "n_dropped": table.rows.len() - n,
+                "standardized": config.standardize,
+                "n_components": n_components,
+                "components": components,
+            }),
+            output_file,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::{read_output, storage_bin, write_csv};
+
+    #[test]
+    fn perfectly_correlated_columns_share_one_component() {
+        storage_bin();
+        let file = write_csv("pca_correlated", "x,y\n1,2\n2,4\n3,6\n4,8\n");
+        let (summary, output) = Principal_Components.process_node(&file, r#"{"columns": ["x", "y"]}"#, &900_036).unwrap();
+
+        let first = &summary["components"][0];
+        assert!((first["explained_variance"].as_f64().unwrap() - 2.0).abs() < 1e-9);
+        assert!((first["explained_variance_ratio"].as_f64().unwrap() - 1.0).abs() < 1e-9);
+        let loading = std::f64::consts::FRAC_1_SQRT_2;
+        assert!((first["loadings"]["x"].as_f64().unwrap() - loading).abs() < 1e-9);
+        assert!((first["loadings"]["y"].as_f64().unwrap() - loading).abs() < 1e-9);
+
+        let rows = read_output(Ok(output));
+        assert_eq!(rows[0], vec!["x", "y", "PC1", "PC2"]);
+    }
+
+    #[test]
+    fn covariance_mode_ranks_by_raw_variance() {
+        storage_bin();
+        let file = write_csv("pca_covariance", "x,y,label\n2,0,a\n-2,0,b\n0,1,c\n0,-1,d\nNA,1,e\n");
+        let (summary, _) = Principal_Components
+            .process_node(&file, r#"{"columns": ["x", "y"], "standardize": false, "n_components": 1}"#, &900_037)
+            .unwrap();
+
+        assert_eq!(summary["n_dropped"], 1);
+        let first = &summary["components"][0];
+        assert!((first["explained_variance"].as_f64().unwrap() - 8.0 / 3.0).abs() < 1e-9);
+        assert!((first["explained_variance_ratio"].as_f64().unwrap() - 0.8).abs() < 1e-9);
+        assert!((first["loadings"]["x"].as_f64().unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn constant_column_cannot_be_standardized() {
+        let file = write_csv("pca_constant", "x,y\n1,5\n2,5\n3,5\n");
+        let result = Principal_Components.process_node(&file, r#"{"columns": ["x", "y"]}"#, &900_038);
+        assert!(result.unwrap_err().to_string().contains("constant"));
+    }
+}
@@ -0,0 +1,227 @@
+use csv::StringRecord;
+use serde::Deserialize;
+use serde_json::json;
+use std::cmp::Ordering;
+use std::error::Error;
+
+use crate::nodes::dialect::open_reader;
+use crate::nodes::output_parquet::infer_type_name;
+use crate::nodes::table::{compare_values, is_missing};
+
+// Rows looked at to infer the schema
+const SCHEMA_SAMPLE_ROWS: usize = 1000;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Contains, // Case-insensitive substring
+    Missing,
+    Present,
+}
+
+#[derive(Debug)]
+pub struct Filter {
+    pub column: String,
+    pub op: FilterOp,
+    pub value: String,
+}
+
+#[derive(Default, Debug)]
+pub struct PreviewOptions {
+    pub offset: usize,
+    pub limit: Option<usize>, // All rows after `offset` when not set
+    pub sort: Option<String>,
+    pub descending: bool,
+    pub filter: Option<Filter>,
+}
+
+impl Filter {
+    fn matches(&self, cell: &str) -> bool {
+        match self.op {
+            FilterOp::Eq => compare_values(cell, &self.value) == Ordering::Equal,
+            FilterOp::Ne => compare_values(cell, &self.value) != Ordering::Equal,
+            FilterOp::Gt => !is_missing(cell) && compare_values(cell, &self.value) == Ordering::Greater,
+            FilterOp::Gte => !is_missing(cell) && compare_values(cell, &self.value) != Ordering::Less,
+            FilterOp::Lt => !is_missing(cell) && compare_values(cell, &self.value) == Ordering::Less,
+            FilterOp::Lte => !is_missing(cell) && compare_values(cell, &self.value) != Ordering::Greater,
+            FilterOp::Contains => cell.to_lowercase().contains(&self.value.to_lowercase()),
+            FilterOp::Missing => is_missing(cell),
+            FilterOp::Present => !is_missing(cell),
+        }
+    }
+}
+
+fn column_position(headers: &StringRecord, column: &str) -> Result<usize, Box<dyn Error>> {
+    headers
+        .iter()
+        .position(|h| h == column)
+        .ok_or_else(|| format!("Table does not contain a column named '{}'", column).into())
+}
+
+// Fail before streaming starts when a filter or sort column does not exist
+pub fn check_columns(file_path: &str, options: &PreviewOptions) -> Result<(), Box<dyn Error>> {
+    let headers = open_reader(file_path)?.headers()?.clone();
+    for column in options.filter.iter().map(|f| &f.column).chain(options.sort.iter()) {
+        column_position(&headers, column)?;
+    }
+    Ok(())
+}
+
+// Visit the matching rows of a table in order, skipping `offset` and stopping after `limit`.
+// Without a sort the file is streamed, with one the matching rows are held in memory to sort them.
+// `emit` returns false to stop reading; the schema and the number of matching rows are returned.
+pub fn scan(
+    file_path: &str,
+    options: &PreviewOptions,
+    mut emit: impl FnMut(serde_json::Value) -> bool,
+) -> Result<(Vec<serde_json::Value>, usize), Box<dyn Error>> {
+    let mut reader = open_reader(file_path)?;
+    let headers = reader.headers()?.clone();
+    let filter = match &options.filter {
+        Some(filter) => Some((column_position(&headers, &filter.column)?, filter)),
+        None => None,
+    };
+    let sort = match &options.sort {
+        Some(column) => Some(column_position(&headers, column)?),
+        None => None,
+    };
+
+    let mut sample: Vec<StringRecord> = Vec::new();
+    let mut matching: Vec<StringRecord> = Vec::new();
+    let mut total = 0;
+    let end = options.limit.map(|limit| options.offset.saturating_add(limit));
+    let to_json = |record: &StringRecord| -> serde_json::Value { headers.iter().zip(record.iter()).collect() };
+
+    for record in reader.records() {
+        let record = record?;
+        if sample.len() < SCHEMA_SAMPLE_ROWS {
+            sample.push(record.clone());
+        }
+        if let Some((index, filter)) = filter {
+            if !filter.matches(record.get(index).unwrap_or("")) {
+                continue;
+            }
+        }
+        if sort.is_some() {
+            matching.push(record);
+        } else if total >= options.offset && end.is_none_or(|end| total < end) && !emit(to_json(&record)) {
+            break;
+        }
+        total += 1;
+    }
+
+    if let Some(index) = sort {
+        // Missing cells go last in either direction
+        matching.sort_by(|a, b| {
+            let (a, b) = (a.get(index).unwrap_or(""), b.get(index).unwrap_or(""));
+            match (is_missing(a), is_missing(b)) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                _ if options.descending => compare_values(b, a),
+                _ => compare_values(a, b),
+            }
+        });
+        let page = matching.iter().skip(options.offset).take(options.limit.unwrap_or(usize::MAX));
+        for record in page {
+            if !emit(to_json(record)) {
+                break;
+            }
+        }
+    }
+
+    let schema = headers
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let values: Vec<&str> = sample.iter().filter_map(|r| r.get(index)).collect();
+            json!({ "name": name, "type": infer_type_name(&values) })
+        })
+        .collect();
+    Ok((schema, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::write_csv;
+
+    fn names(file: &str, options: &PreviewOptions) -> (Vec<String>, usize) {
+        let mut rows: Vec<String> = Vec::new();
+        let (_, total) = scan(file, options, |row| {
+            rows.push(row["name"].as_str().unwrap().to_string());
+            true
+        })
+        .unwrap();
+        (rows, total)
+    }
+
+    fn filter(column: &str, op: FilterOp, value: &str) -> Option<Filter> {
+        Some(Filter { column: column.to_string(), op, value: value.to_string() })
+    }
+
+    const PEOPLE: &str = "name,age\nann,30\nbob,9\ncy,NA\ndee,100\nEd,9\n";
+
+    #[test]
+    fn pages_without_sorting() {
+        let file = write_csv("preview_pages", PEOPLE);
+        let (rows, total) = names(&file, &PreviewOptions { offset: 1, limit: Some(2), ..Default::default() });
+        assert_eq!((rows, total), (vec!["bob".to_string(), "cy".to_string()], 5));
+    }
+
+    #[test]
+    fn sorts_numerically_with_missing_cells_last() {
+        let file = write_csv("preview_sort", PEOPLE);
+        let ascending = PreviewOptions { sort: Some("age".to_string()), ..Default::default() };
+        assert_eq!(names(&file, &ascending).0, vec!["bob", "Ed", "ann", "dee", "cy"]);
+        let descending = PreviewOptions { sort: Some("age".to_string()), descending: true, limit: Some(2), ..Default::default() };
+        assert_eq!(names(&file, &descending).0, vec!["dee", "ann"]);
+    }
+
+    #[test]
+    fn mixed_columns_sort_numbers_before_text() {
+        let file = write_csv("preview_mixed", "name,code\na,9\nb,10\nc,5a\nd,abc\ne,NA\n");
+        let ascending = PreviewOptions { sort: Some("code".to_string()), ..Default::default() };
+        assert_eq!(names(&file, &ascending).0, vec!["a", "b", "c", "d", "e"]);
+        let descending = PreviewOptions { sort: Some("code".to_string()), descending: true, ..Default::default() };
+        assert_eq!(names(&file, &descending).0, vec!["d", "c", "b", "a", "e"]);
+    }
+
+    #[test]
+    fn filters_count_only_matching_rows() {
+        let file = write_csv("preview_filter", PEOPLE);
+        let run = |filter: Option<Filter>| names(&file, &PreviewOptions { filter, ..Default::default() });
+        assert_eq!(run(filter("age", FilterOp::Gte, "30")), (vec!["ann".to_string(), "dee".to_string()], 2));
+        assert_eq!(run(filter("age", FilterOp::Eq, "9.0")).1, 2);
+        assert_eq!(run(filter("age", FilterOp::Lt, "50")).1, 3);
+        assert_eq!(run(filter("age", FilterOp::Missing, "")).0, vec!["cy"]);
+        assert_eq!(run(filter("name", FilterOp::Contains, "E")).0, vec!["dee", "Ed"]);
+    }
+
+    #[test]
+    fn emit_can_stop_the_scan_and_schema_is_inferred() {
+        let file = write_csv("preview_stop", PEOPLE);
+        let mut seen = 0;
+        let (schema, _) = scan(&file, &PreviewOptions::default(), |_| {
+            seen += 1;
+            seen < 2
+        })
+        .unwrap();
+        assert_eq!(seen, 2);
+        assert_eq!(schema, vec![json!({ "name": "name", "type": "string" }), json!({ "name": "age", "type": "integer" })]);
+    }
+
+    #[test]
+    fn unknown_columns_fail_before_streaming() {
+        let file = write_csv("preview_columns", PEOPLE);
+        let options = PreviewOptions { sort: Some("height".to_string()), ..Default::default() };
+        assert!(check_columns(&file, &options).unwrap_err().to_string().contains("'height'"));
+        assert!(check_columns(&file, &PreviewOptions { filter: filter("age", FilterOp::Present, ""), ..Default::default() }).is_ok());
+    }
+}
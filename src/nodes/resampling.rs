@@ -0,0 +1,413 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::Deserialize;
+use serde_json::json;
+use statrs::distribution::{ContinuousCDF, Normal};
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use crate::nodes::descriptive::{mean, median, quantile_sorted, sorted, std_dev};
+use crate::nodes::table::{is_missing, parse_number, Table};
+
+pub struct Resampling;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum Statistic {
+    Mean,
+    Median,
+    MeanDifference,
+    Correlation,
+}
+
+#[derive(Deserialize)]
+struct ResamplingConfig {
+    statistic: Statistic,
+    column: String,
+    #[serde(default)]
+    column_y: Option<String>, // Second column for `correlation`
+    #[serde(default)]
+    group_column: Option<String>, // Group labels for `mean-difference`
+    #[serde(default)]
+    groups: Option<(String, String)>, // The two groups compared, the first two levels when not set
+    #[serde(default = "default_resamples")]
+    resamples: usize,
+    #[serde(default)]
+    seed: u64,
+    #[serde(default = "default_confidence_level")]
+    confidence_level: f64,
+    #[serde(default)]
+    null_value: f64, // Location tested by the sign-flip test for `mean` and `median`
+}
+
+// Bootstrap and permutation each draw `resamples` copies of the data
+const MAX_RESAMPLED_VALUES: usize = 100_000_000;
+
+fn default_resamples() -> usize {
+    2000
+}
+
+fn default_confidence_level() -> f64 {
+    0.95
+}
+
+// The observations a statistic is computed on: one sample, two groups, or paired columns
+#[derive(Clone)]
+enum Sample {
+    One(Vec<f64>),
+    Two(Vec<f64>, Vec<f64>),
+    Paired(Vec<f64>, Vec<f64>),
+}
+
+impl Resampling {
+    // Bootstrap confidence intervals and a permutation p-value, resamples are spread across CPU cores
+    pub fn process_node(&self, file1: &str, data: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+        let config: ResamplingConfig = serde_json::from_str(data)?;
+        if config.confidence_level <= 0.0 || config.confidence_level >= 1.0 {
+            return Err("confidence_level must be between 0 and 1".into());
+        }
+        if config.resamples == 0 {
+            return Err("resamples must be positive".into());
+        }
+
+        let table = Table::from_path(file1)?;
+        let sample = load_sample(&table, &config)?;
+        let n_values = match &sample {
+            Sample::One(x) | Sample::Paired(x, _) => x.len(),
+            Sample::Two(a, b) => a.len() + b.len(),
+        };
+        if config.resamples.saturating_mul(n_values) > MAX_RESAMPLED_VALUES {
+            return Err(format!(
+                "{} resamples of {} values is too much work, keep resamples × rows under {}",
+                config.resamples, n_values, MAX_RESAMPLED_VALUES
+            )
+            .into());
+        }
+        resample(&config, &sample)
+    }
+}
+
+// Bootstrap intervals and the permutation test for an already loaded sample
+fn resample(config: &ResamplingConfig, sample: &Sample) -> Result<serde_json::Value, Box<dyn Error>> {
+    let estimate = compute(config.statistic, sample);
+
+    // Each resample gets its own generator derived from the seed, so results do not depend on thread scheduling
+    let rng_for = |i: usize| StdRng::seed_from_u64(config.seed.wrapping_mul(0x9E37_79B9_7F4A_7C15).wrapping_add(i as u64));
+
+    let bootstrap: Vec<f64> = (0..config.resamples)
+        .into_par_iter()
+        .map(|i| compute(config.statistic, &bootstrap_sample(sample, &mut rng_for(i))))
+        .filter(|v| v.is_finite())
+        .collect();
+    let bootstrap = sorted(&bootstrap);
+    if bootstrap.is_empty() {
+        return Err("The statistic could not be computed on any bootstrap resample".into());
+    }
+
+    let alpha = 1.0 - config.confidence_level;
+    let normal = Normal::new(0.0, 1.0)?;
+    let percentile = (quantile_sorted(&bootstrap, alpha / 2.0), quantile_sorted(&bootstrap, 1.0 - alpha / 2.0));
+
+    // BCa: bias correction from the share of resamples below the estimate, acceleration from the jackknife
+    let below = bootstrap.iter().filter(|v| **v < estimate).count() as f64 / bootstrap.len() as f64;
+    let z0 = normal.inverse_cdf(below.clamp(1e-10, 1.0 - 1e-10));
+    let jackknife = jackknife(config.statistic, sample);
+    let jackknife_mean = mean(&jackknife);
+    let numerator: f64 = jackknife.iter().map(|v| (jackknife_mean - v).powi(3)).sum();
+    let denominator: f64 = jackknife.iter().map(|v| (jackknife_mean - v).powi(2)).sum::<f64>().powf(1.5);
+    let acceleration = if denominator > 0.0 { numerator / (6.0 * denominator) } else { 0.0 };
+    let adjusted = |p: f64| {
+        let z = normal.inverse_cdf(p);
+        normal.cdf(z0 + (z0 + z) / (1.0 - acceleration * (z0 + z)))
+    };
+    let bca = (
+        quantile_sorted(&bootstrap, adjusted(alpha / 2.0)),
+        quantile_sorted(&bootstrap, adjusted(1.0 - alpha / 2.0)),
+    );
+
+    let observed = permutation_statistic(config.statistic, sample, config.null_value);
+    let extreme = (0..config.resamples)
+        .into_par_iter()
+        .filter(|i| {
+            let mut rng = rng_for(config.resamples + i);
+            let permuted = permutation_statistic(config.statistic, &permute(sample, config.null_value, &mut rng), config.null_value);
+            permuted.abs() >= observed.abs()
+        })
+        .count();
+    let p_value = (extreme + 1) as f64 / (config.resamples + 1) as f64;
+
+    let n = match sample {
+        Sample::One(x) => json!(x.len()),
+        Sample::Two(a, b) => json!([a.len(), b.len()]),
+        Sample::Paired(x, _) => json!(x.len()),
+    };
+
+    Ok(json!({
+        "statistic": match config.statistic {
+            Statistic::Mean => "mean",
+            Statistic::Median => "median",
+            Statistic::MeanDifference => "mean-difference",
+            Statistic::Correlation => "correlation",
+        },
+        "estimate": estimate,
+        "n": n,
+        "resamples": config.resamples,
+        "seed": config.seed,
+        "confidence_level": config.confidence_level,
+        "bootstrap": {
+            "std_error": std_dev(&bootstrap),
+            "bias": mean(&bootstrap) - estimate,
+            "percentile": { "lower": percentile.0, "upper": percentile.1 },
+            "bca": { "lower": bca.0, "upper": bca.1, "z0": z0, "acceleration": acceleration },
+        },
+        "permutation": {
+            "p_value": p_value,
+            "null_value": if matches!(config.statistic, Statistic::Mean | Statistic::Median) { Some(config.null_value) } else { None },
+        },
+    }))
+}
+
+fn load_sample(table: &Table, config: &ResamplingConfig) -> Result<Sample, Box<dyn Error>> {
+    let index = table.column_index(&config.column)?;
+    let sample = match config.statistic {
+        Statistic::Mean | Statistic::Median => {
+            Sample::One(table.numeric_column(index).into_iter().flatten().collect())
+        }
+        Statistic::Correlation => {
+            let column_y = config.column_y.as_ref().ok_or("correlation needs `column_y`")?;
+            let (_, rows) = table.complete_numeric_rows(&[index, table.column_index(column_y)?]);
+            Sample::Paired(rows.iter().map(|r| r[0]).collect(), rows.iter().map(|r| r[1]).collect())
+        }
+        Statistic::MeanDifference => {
+            let group_column = config.group_column.as_ref().ok_or("mean-difference needs `group_column`")?;
+            let group_index = table.column_index(group_column)?;
+            let (first, second) = match &config.groups {
+                Some(groups) => groups.clone(),
+                None => {
+                    let levels: BTreeSet<&str> = table
+                        .rows
+                        .iter()
+                        .filter_map(|row| row.get(group_index))
+                        .filter(|v| !is_missing(v))
+                        .map(|v| v.trim())
+                        .collect();
+                    let mut levels = levels.into_iter();
+                    match (levels.next(), levels.next()) {
+                        (Some(a), Some(b)) => (a.to_string(), b.to_string()),
+                        _ => return Err(format!("Column '{}' needs at least two groups", group_column).into()),
+                    }
+                }
+            };
+            let mut a: Vec<f64> = Vec::new();
+            let mut b: Vec<f64> = Vec::new();
+            for row in &table.rows {
+                let value = row.get(index).and_then(|v| parse_number(v));
+                match (row.get(group_index).map(|g| g.trim()), value) {
+                    (Some(g), Some(v)) if g == first => a.push(v),
+                    (Some(g), Some(v)) if g == second => b.push(v),
+                    _ => {}
+                }
+            }
+            Sample::Two(a, b)
+        }
+    };
+
+    let too_small = match &sample {
+        Sample::One(x) => x.len() < 2,
+        Sample::Two(a, b) => a.len() < 2 || b.len() < 2,
+        Sample::Paired(x, _) => x.len() < 3,
+    };
+    if too_small {
+        return Err("Not enough values to resample".into());
+    }
+    Ok(sample)
+}
+
+fn compute(statistic: Statistic, sample: &Sample) -> f64 {
+    match (statistic, sample) {
+        (Statistic::Mean, Sample::One(x)) => mean(x),
+        (Statistic::Median, Sample::One(x)) => median(x),
+        (Statistic::MeanDifference, Sample::Two(a, b)) => mean(a) - mean(b),
+        (Statistic::Correlation, Sample::Paired(x, y)) => {
+            let (mx, my) = (mean(x), mean(y));
+            let covariance: f64 = x.iter().zip(y.iter()).map(|(a, b)| (a - mx) * (b - my)).sum();
+            let vx: f64 = x.iter().map(|a| (a - mx).powi(2)).sum();
+            let vy: f64 = y.iter().map(|b| (b - my).powi(2)).sum();
+            covariance / (vx * vy).sqrt()
+        }
+        _ => f64::NAN,
+    }
+}
+
+fn draw(values: &[f64], rng: &mut StdRng) -> Vec<f64> {
+    (0..values.len()).map(|_| values[rng.random_range(0..values.len())]).collect()
+}
+
+fn bootstrap_sample(sample: &Sample, rng: &mut StdRng) -> Sample {
+    match sample {
+        Sample::One(x) => Sample::One(draw(x, rng)),
+        // Groups are resampled separately so their sizes stay fixed
+        Sample::Two(a, b) => Sample::Two(draw(a, rng), draw(b, rng)),
+        Sample::Paired(x, y) => {
+            let picks: Vec<usize> = (0..x.len()).map(|_| rng.random_range(0..x.len())).collect();
+            Sample::Paired(picks.iter().map(|i| x[*i]).collect(), picks.iter().map(|i| y[*i]).collect())
+        }
+    }
+}
+
+// Leave-one-out estimates, for two groups every observation of either group is left out once.
+// Each is updated from totals of the full sample rather than recomputed, so this is O(n) after sorting.
+fn jackknife(statistic: Statistic, sample: &Sample) -> Vec<f64> {
+    let leave_one_out_means = |values: &[f64]| -> Vec<f64> {
+        let total: f64 = values.iter().sum();
+        let n = (values.len() - 1) as f64;
+        values.iter().map(|v| (total - v) / n).collect()
+    };
+    match (statistic, sample) {
+        (Statistic::Mean, Sample::One(x)) => leave_one_out_means(x),
+        (Statistic::Median, Sample::One(x)) => {
+            // Dropping the value at sorted position k shifts every later value down one place
+            let ordered = sorted(x);
+            let m = ordered.len() - 1;
+            let at = |k: usize, j: usize| if j < k { ordered[j] } else { ordered[j + 1] };
+            (0..ordered.len())
+                .map(|k| if m % 2 == 1 { at(k, m / 2) } else { (at(k, m / 2 - 1) + at(k, m / 2)) / 2.0 })
+                .collect()
+        }
+        (Statistic::MeanDifference, Sample::Two(a, b)) => {
+            let (mean_a, mean_b) = (mean(a), mean(b));
+            let mut estimates: Vec<f64> = leave_one_out_means(a).iter().map(|m| m - mean_b).collect();
+            estimates.extend(leave_one_out_means(b).iter().map(|m| mean_a - m));
+            estimates
+        }
+        (Statistic::Correlation, Sample::Paired(x, y)) => {
+            // Sums of the centred values, with one pair taken out each time
+            let (mx, my) = (mean(x), mean(y));
+            let centred: Vec<(f64, f64)> = x.iter().zip(y.iter()).map(|(a, b)| (a - mx, b - my)).collect();
+            let (sx, sy) = centred.iter().fold((0.0, 0.0), |(sx, sy), (a, b)| (sx + a, sy + b));
+            let sxx: f64 = centred.iter().map(|(a, _)| a * a).sum();
+            let syy: f64 = centred.iter().map(|(_, b)| b * b).sum();
+            let sxy: f64 = centred.iter().map(|(a, b)| a * b).sum();
+            let n = (centred.len() - 1) as f64;
+            centred
+                .iter()
+                .map(|(a, b)| {
+                    let (sx, sy) = (sx - a, sy - b);
+                    let covariance = (sxy - a * b) - sx * sy / n;
+                    let vx = (sxx - a * a) - sx * sx / n;
+                    let vy = (syy - b * b) - sy * sy / n;
+                    covariance / (vx * vy).sqrt()
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+// Test statistic under the null: distance of the location from `null_value`, or the statistic itself
+fn permutation_statistic(statistic: Statistic, sample: &Sample, null_value: f64) -> f64 {
+    match statistic {
+        Statistic::Mean | Statistic::Median => compute(statistic, sample) - null_value,
+        _ => compute(statistic, sample),
+    }
+}
+
+// Sign flips around the null value for one sample, shuffled labels for two groups, shuffled pairing for correlation
+fn permute(sample: &Sample, null_value: f64, rng: &mut StdRng) -> Sample {
+    match sample {
+        Sample::One(x) => Sample::One(
+            x.iter()
+                .map(|v| {
+                    let deviation = v - null_value;
+                    null_value + if rng.random::<bool>() { deviation } else { -deviation }
+                })
+                .collect(),
+        ),
+        Sample::Two(a, b) => {
+            let mut pooled: Vec<f64> = a.iter().chain(b.iter()).copied().collect();
+            pooled.shuffle(rng);
+            let (first, second) = pooled.split_at(a.len());
+            Sample::Two(first.to_vec(), second.to_vec())
+        }
+        Sample::Paired(x, y) => {
+            let mut shuffled = y.clone();
+            shuffled.shuffle(rng);
+            Sample::Paired(x.clone(), shuffled)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::write_csv;
+
+    // Leave each observation out and recompute, the definition the closed forms must match
+    fn brute_force_jackknife(statistic: Statistic, sample: &Sample) -> Vec<f64> {
+        let leave_out = |values: &[f64], i: usize| -> Vec<f64> {
+            values.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, v)| *v).collect()
+        };
+        match sample {
+            Sample::One(x) => (0..x.len()).map(|i| compute(statistic, &Sample::One(leave_out(x, i)))).collect(),
+            Sample::Two(a, b) => (0..a.len())
+                .map(|i| compute(statistic, &Sample::Two(leave_out(a, i), b.clone())))
+                .chain((0..b.len()).map(|i| compute(statistic, &Sample::Two(a.clone(), leave_out(b, i)))))
+                .collect(),
+            Sample::Paired(x, y) => (0..x.len()).map(|i| compute(statistic, &Sample::Paired(leave_out(x, i), leave_out(y, i)))).collect(),
+        }
+    }
+
+    fn assert_same(statistic: Statistic, sample: Sample) {
+        let mut fast = jackknife(statistic, &sample);
+        let mut slow = brute_force_jackknife(statistic, &sample);
+        // The median version works in sorted order, the acceleration only needs the multiset
+        fast.sort_by(|a, b| a.total_cmp(b));
+        slow.sort_by(|a, b| a.total_cmp(b));
+        assert_eq!(fast.len(), slow.len());
+        for (a, b) in fast.iter().zip(&slow) {
+            assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn closed_form_jackknife_matches_recomputation() {
+        let x = vec![3.0, 1.0, 4.0, 1.0, 5.0, 9.0, 2.0, 6.0];
+        let y = vec![2.0, 7.0, 1.0, 8.0, 2.0, 8.0, 1.0, 8.0];
+        assert_same(Statistic::Mean, Sample::One(x.clone()));
+        assert_same(Statistic::Median, Sample::One(x.clone()));
+        assert_same(Statistic::Median, Sample::One(x[..7].to_vec()));
+        assert_same(Statistic::MeanDifference, Sample::Two(x.clone(), y[..5].to_vec()));
+        assert_same(Statistic::Correlation, Sample::Paired(x, y));
+    }
+
+    #[test]
+    fn seeded_runs_are_reproducible() {
+        let file = write_csv("resampling_seeded", "x\n1\n2\n3\n4\n5\n6\n7\n8\n");
+        let data = r#"{"statistic": "mean", "column": "x", "resamples": 500, "seed": 3}"#;
+        let first = Resampling.process_node(&file, data).unwrap();
+        let second = Resampling.process_node(&file, data).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first["estimate"], 4.5);
+        let lower = first["bootstrap"]["percentile"]["lower"].as_f64().unwrap();
+        let upper = first["bootstrap"]["percentile"]["upper"].as_f64().unwrap();
+        assert!(lower < 4.5 && 4.5 < upper);
+    }
+
+    #[test]
+    fn resampling_work_is_capped() {
+        let file = write_csv("resampling_capped", "x\n1\n2\n3\n");
+        let data = r#"{"statistic": "mean", "column": "x", "resamples": 1000000000}"#;
+        assert!(Resampling.process_node(&file, data).unwrap_err().to_string().contains("too much work"));
+    }
+
+    #[test]
+    fn shifted_groups_have_a_small_permutation_p_value() {
+        let file = write_csv("resampling_groups", "g,x\na,1\na,2\na,3\na,4\na,5\nb,11\nb,12\nb,13\nb,14\nb,15\n");
+        let data = r#"{"statistic": "mean-difference", "column": "x", "group_column": "g", "resamples": 999}"#;
+        let result = Resampling.process_node(&file, data).unwrap();
+        assert_eq!(result["estimate"], -10.0);
+        assert!(result["permutation"]["p_value"].as_f64().unwrap() < 0.05);
+    }
+}
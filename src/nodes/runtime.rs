@@ -0,0 +1,9 @@
+use std::error::Error;
+use std::future::Future;
+
+// Nodes run synchronously on a blocking thread (see `process_nodes`), so nodes with async drivers run
+// them on a small runtime of their own rather than calling back into the server runtime
+pub fn block_on<F: Future>(future: F) -> Result<F::Output, Box<dyn Error>> {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    Ok(runtime.block_on(future))
+}
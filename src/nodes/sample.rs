@@ -0,0 +1,227 @@
+use rand::rngs::StdRng;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::SeedableRng;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use crate::nodes::table::Table;
+
+pub struct Sample_Rows;
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum SampleMode {
+    Random,
+    Stratified,
+    Split,
+}
+
+#[derive(Deserialize)]
+struct SampleConfig {
+    #[serde(default = "default_mode")]
+    mode: SampleMode,
+    #[serde(default)]
+    n: Option<usize>, // Rows to keep, per stratum in `stratified` mode
+    #[serde(default)]
+    fraction: Option<f64>, // Share of rows to keep, used when `n` is not set
+    #[serde(default)]
+    strata_column: Option<String>, // Required for `stratified`, optional for `split` to keep group shares in both ports
+    #[serde(default = "default_test_fraction")]
+    test_fraction: f64,
+    #[serde(default)]
+    seed: u64,
+}
+
+fn default_mode() -> SampleMode {
+    SampleMode::Random
+}
+
+fn default_test_fraction() -> f64 {
+    0.2
+}
+
+// What a `sample` node produced: the main output, and the `test` port in split mode
+pub struct SampleOutput {
+    pub summary: serde_json::Value,
+    pub path: String,
+    pub test_path: Option<String>,
+}
+
+impl Sample_Rows {
+    // Seeded row sampling, kept rows stay in their original order
+    pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<SampleOutput, Box<dyn Error>> {
+        let config: SampleConfig = serde_json::from_str(data)?;
+        if let Some(fraction) = config.fraction {
+            if !(0.0..=1.0).contains(&fraction) {
+                return Err("fraction must be between 0 and 1".into());
+            }
+        }
+        if config.mode == SampleMode::Split && (config.test_fraction <= 0.0 || config.test_fraction >= 1.0) {
+            return Err("test_fraction must be between 0 and 1".into());
+        }
+
+        let table = Table::from_path(file1)?;
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let strata = match &config.strata_column {
+            Some(column) => {
+                let index = table.column_index(column)?;
+                let mut strata: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+                for (position, row) in table.rows.iter().enumerate() {
+                    let key = row.get(index).map(|v| v.trim().to_string()).unwrap_or_default();
+                    strata.entry(key).or_default().push(position);
+                }
+                strata
+            }
+            None if config.mode == SampleMode::Stratified => return Err("Stratified sampling needs `strata_column`".into()),
+            None => BTreeMap::from([(String::new(), (0..table.rows.len()).collect())]),
+        };
+
+        match config.mode {
+            SampleMode::Random | SampleMode::Stratified => {
+                if config.n.is_none() && config.fraction.is_none() {
+                    return Err("Set either `n` or `fraction`".into());
+                }
+                let mut kept: Vec<usize> = Vec::new();
+                let mut per_stratum = serde_json::Map::new();
+                for (key, positions) in &strata {
+                    let take = match (config.n, config.fraction) {
+                        (Some(n), _) => n.min(positions.len()),
+                        (None, Some(fraction)) => (fraction * positions.len() as f64).round() as usize,
+                        (None, None) => 0,
+                    };
+                    let chosen: Vec<usize> = positions.choose_multiple(&mut rng, take).copied().collect();
+                    per_stratum.insert(key.clone(), json!({ "rows": positions.len(), "sampled": chosen.len() }));
+                    kept.extend(chosen);
+                }
+                kept.sort_unstable();
+
+                let output_file = format!("./storage_bin/sample_{}.csv", node_id);
+                subset(&table, &kept).write_to_path(&output_file)?;
+                println!("Sample written to {}", output_file);
+
+                let mut summary = json!({
+                    "mode": if config.mode == SampleMode::Random { "random" } else { "stratified" },
+                    "seed": config.seed,
+                    "input_rows": table.rows.len(),
+                    "output_rows": kept.len(),
+                });
+                if config.mode == SampleMode::Stratified {
+                    summary["strata"] = json!(per_stratum);
+                }
+                Ok(SampleOutput { summary, path: output_file, test_path: None })
+            }
+            SampleMode::Split => {
+                // Shuffle within each stratum and send the first share of it to the test port
+                let mut train: Vec<usize> = Vec::new();
+                let mut test: Vec<usize> = Vec::new();
+                for positions in strata.values() {
+                    let mut shuffled = positions.clone();
+                    shuffled.shuffle(&mut rng);
+                    let test_rows = (config.test_fraction * shuffled.len() as f64).round() as usize;
+                    test.extend_from_slice(&shuffled[..test_rows]);
+                    train.extend_from_slice(&shuffled[test_rows..]);
+                }
+                train.sort_unstable();
+                test.sort_unstable();
+
+                let train_file = format!("./storage_bin/split_train_{}.csv", node_id);
+                let test_file = format!("./storage_bin/split_test_{}.csv", node_id);
+                subset(&table, &train).write_to_path(&train_file)?;
+                subset(&table, &test).write_to_path(&test_file)?;
+                println!("Train split written to {}, test split written to {}", train_file, test_file);
+
+                Ok(SampleOutput {
+                    summary: json!({
+                        "mode": "split",
+                        "seed": config.seed,
+                        "input_rows": table.rows.len(),
+                        "train_rows": train.len(),
+                        "test_rows": test.len(),
+                        "stratified_by": config.strata_column,
+                        "ports": { "train": train_file, "test": test_file },
+                    }),
+                    path: train_file,
+                    test_path: Some(test_file),
+                })
+            }
+        }
+    }
+}
+
+fn subset(table: &Table, positions: &[usize]) -> Table {
+    Table {
+        headers: table.headers.clone(),
+        rows: positions.iter().map(|p| table.rows[*p].clone()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::{storage_bin, write_csv};
+
+    fn rows(path: &str) -> Vec<Vec<String>> {
+        Table::from_path(path).unwrap().rows
+    }
+
+    fn fixture(name: &str) -> String {
+        storage_bin();
+        let mut contents = String::from("id,group\n");
+        for i in 0..20 {
+            contents.push_str(&format!("{},{}\n", i, if i < 15 { "a" } else { "b" }));
+        }
+        write_csv(name, &contents)
+    }
+
+    #[test]
+    fn random_sample_is_seeded_and_keeps_row_order() {
+        let file = fixture("sample_random");
+        let first = Sample_Rows.process_node(&file, r#"{"n": 5, "seed": 7}"#, &900_039).unwrap();
+        let kept = rows(&first.path);
+        assert_eq!(kept.len(), 5);
+        let ids: Vec<usize> = kept.iter().map(|r| r[0].parse().unwrap()).collect();
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+
+        let second = Sample_Rows.process_node(&file, r#"{"n": 5, "seed": 7}"#, &900_039).unwrap();
+        assert_eq!(rows(&second.path), kept);
+    }
+
+    #[test]
+    fn stratified_sample_takes_a_fraction_of_each_group() {
+        let file = fixture("sample_stratified");
+        let output = Sample_Rows
+            .process_node(&file, r#"{"mode": "stratified", "fraction": 0.2, "strata_column": "group"}"#, &900_040)
+            .unwrap();
+        assert_eq!(output.summary["strata"]["a"]["sampled"], 3);
+        assert_eq!(output.summary["strata"]["b"]["sampled"], 1);
+        assert_eq!(rows(&output.path).len(), 4);
+    }
+
+    #[test]
+    fn split_partitions_every_row_once() {
+        let file = fixture("sample_split");
+        let output = Sample_Rows
+            .process_node(&file, r#"{"mode": "split", "test_fraction": 0.2, "strata_column": "group", "seed": 1}"#, &900_041)
+            .unwrap();
+        let train = rows(&output.path);
+        let test = rows(output.test_path.as_ref().unwrap());
+        assert_eq!((train.len(), test.len()), (16, 4));
+        assert_eq!(test.iter().filter(|r| r[1] == "b").count(), 1);
+
+        let mut ids: Vec<usize> = train.iter().chain(&test).map(|r| r[0].parse().unwrap()).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn rejects_invalid_configs() {
+        let file = fixture("sample_invalid");
+        let error = |data: &str| Sample_Rows.process_node(&file, data, &900_042).err().unwrap().to_string();
+        assert!(error(r#"{"fraction": 1.5}"#).contains("between 0 and 1"));
+        assert!(error(r#"{"mode": "split", "test_fraction": 1.0}"#).contains("between 0 and 1"));
+        assert!(error(r#"{"mode": "stratified", "n": 2}"#).contains("strata_column"));
+        assert!(error(r#"{}"#).contains("`n` or `fraction`"));
+    }
+}
@@ -0,0 +1,226 @@
+use calamine::{open_workbook_auto, Data, Reader};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::Path;
+
+use crate::nodes::datetime::{DATE_OUTPUT_FORMAT, OUTPUT_FORMAT};
+use crate::nodes::dialect::{self, Dialect};
+use crate::nodes::table::Table;
+
+const SPREADSHEET_EXTENSIONS: [&str; 5] = ["xlsx", "xlsm", "xlsb", "xls", "ods"];
+
+// Which part of the workbook becomes the table
+#[derive(Deserialize, Default, Debug)]
+pub struct SpreadsheetOptions {
+    #[serde(default)]
+    pub sheet: Option<String>, // Sheet name, the first sheet when not set
+    #[serde(default)]
+    pub range: Option<String>, // Cell range such as `B3:F200`, the used area of the sheet when not set
+    #[serde(default)]
+    pub header_row: Option<usize>, // 1-based row within the range holding the column names, 0 for none; rows above it are skipped
+}
+
+pub fn is_spreadsheet(file_path: &str) -> bool {
+    Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| SPREADSHEET_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// The converted table lives next to the workbook, so a different sheet can be picked later
+pub fn converted_path(file_path: &str) -> String {
+    format!("{}.csv", file_path)
+}
+
+// Workbook behind a converted table, if `csv_path` is one
+pub fn source_path(csv_path: &str) -> Option<String> {
+    csv_path
+        .strip_suffix(".csv")
+        .filter(|source| is_spreadsheet(source) && Path::new(source).exists())
+        .map(|source| source.to_string())
+}
+
+// Write the chosen sheet and range as CSV in the default dialect and describe what was imported
+pub fn convert_to_csv(file_path: &str, options: &SpreadsheetOptions) -> Result<(String, serde_json::Value), Box<dyn Error>> {
+    let mut workbook = open_workbook_auto(file_path)?;
+    let sheets = workbook.sheet_names();
+    let sheet = match &options.sheet {
+        Some(sheet) if sheets.contains(sheet) => sheet.clone(),
+        Some(sheet) => return Err(format!("Workbook has no sheet named '{}', sheets are {:?}", sheet, sheets).into()),
+        None => sheets.first().cloned().ok_or("Workbook has no sheets")?,
+    };
+
+    let mut cells = workbook.worksheet_range(&sheet)?;
+    if let Some(range) = &options.range {
+        let (start, end) = parse_range(range)?;
+        cells = cells.range(start, end);
+    }
+
+    let mut rows: Vec<Vec<String>> = cells.rows().map(|row| row.iter().map(cell_to_string).collect()).collect();
+    let header_row = options.header_row.unwrap_or(1);
+    if header_row > rows.len() {
+        return Err(format!("Header row {} is outside the {} rows of the range", header_row, rows.len()).into());
+    }
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let headers = if header_row == 0 {
+        (1..=width).map(|i| format!("column_{}", i)).collect()
+    } else {
+        let names = rows[header_row - 1].clone();
+        rows.drain(..header_row);
+        unique_headers(&names, width)
+    };
+
+    let before = rows.len();
+    rows.retain(|row| row.iter().any(|v| !v.is_empty()));
+    let table = Table { headers, rows };
+
+    let output_file = converted_path(file_path);
+    table.write_to_path(&output_file)?;
+    // Overwrite any sniffed dialect, the converted file is always plain CSV with a header row
+    dialect::save(&output_file, &Dialect::default())?;
+    println!("Sheet '{}' of {} written to {}", sheet, file_path, output_file);
+
+    Ok((
+        output_file,
+        json!({
+            "sheet": sheet,
+            "sheets": sheets,
+            "rows": table.rows.len(),
+            "empty_rows_dropped": before - table.rows.len(),
+            "columns": table.headers,
+        }),
+    ))
+}
+
+fn cell_to_string(cell: &Data) -> String {
+    match cell {
+        Data::Empty | Data::Error(_) => String::new(),
+        Data::String(value) | Data::DateTimeIso(value) | Data::DurationIso(value) => value.clone(),
+        Data::Int(value) => value.to_string(),
+        Data::Float(value) => value.to_string(),
+        Data::Bool(value) => value.to_string(),
+        Data::DateTime(value) => match value.as_datetime() {
+            Some(datetime) if datetime.time() == chrono::NaiveTime::MIN => datetime.format(DATE_OUTPUT_FORMAT).to_string(),
+            Some(datetime) => datetime.format(OUTPUT_FORMAT).to_string(),
+            None => value.as_f64().to_string(),
+        },
+    }
+}
+
+// Blank names become `column_<n>` and repeats get a numeric suffix, nodes look columns up by name
+fn unique_headers(names: &[String], width: usize) -> Vec<String> {
+    let mut seen: HashSet<String> = HashSet::new();
+    (0..width)
+        .map(|i| {
+            let base = match names.get(i).map(|n| n.trim()) {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => format!("column_{}", i + 1),
+            };
+            let mut name = base.clone();
+            let mut suffix = 2;
+            while !seen.insert(name.clone()) {
+                name = format!("{}_{}", base, suffix);
+                suffix += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+// Zero-based (row, column) of a cell
+type CellPosition = (u32, u32);
+
+// `B3:F200` to its top-left and bottom-right cells
+fn parse_range(range: &str) -> Result<(CellPosition, CellPosition), Box<dyn Error>> {
+    let (start, end) = range.split_once(':').ok_or_else(|| format!("Range '{}' should look like A1:D20", range))?;
+    let (start, end) = (parse_cell(start)?, parse_cell(end)?);
+    if start.0 > end.0 || start.1 > end.1 {
+        return Err(format!("Range '{}' ends before it starts", range).into());
+    }
+    Ok((start, end))
+}
+
+fn parse_cell(cell: &str) -> Result<CellPosition, Box<dyn Error>> {
+    let cell = cell.trim().replace('$', "").to_uppercase();
+    let split = cell.find(|c: char| c.is_ascii_digit()).ok_or_else(|| format!("Cell '{}' has no row number", cell))?;
+    let (letters, digits) = cell.split_at(split);
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(format!("Cell '{}' has no column letters", cell).into());
+    }
+    let column = letters.chars().fold(0u32, |acc, c| acc * 26 + (c as u32 - 'A' as u32 + 1));
+    let row: u32 = digits.parse().map_err(|_| format!("Cell '{}' has an invalid row number", cell))?;
+    if row == 0 {
+        return Err(format!("Cell '{}' has an invalid row number", cell).into());
+    }
+    Ok((row - 1, column - 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_xlsxwriter::Workbook;
+
+    fn workbook(name: &str) -> String {
+        let directory = std::env::temp_dir().join(format!("istat_tests_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(format!("{}.xlsx", name)).to_string_lossy().into_owned();
+
+        let mut workbook = Workbook::new();
+        workbook.add_worksheet().set_name("notes").unwrap().write_string(0, 0, "ignored").unwrap();
+        let sheet = workbook.add_worksheet().set_name("data").unwrap();
+        sheet.write_string(1, 1, "Report title").unwrap();
+        for (column, name) in ["id", "", "id"].iter().enumerate() {
+            sheet.write_string(3, column as u16 + 1, *name).unwrap();
+        }
+        sheet.write_number(4, 1, 1.0).unwrap();
+        sheet.write_number(4, 2, 2.5).unwrap();
+        sheet.write_boolean(4, 3, true).unwrap();
+        sheet.write_number(6, 1, 3.0).unwrap();
+        workbook.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn converts_a_range_below_a_title() {
+        let path = workbook("spreadsheet_range");
+        let options = SpreadsheetOptions { sheet: Some("data".to_string()), range: Some("B4:D7".to_string()), header_row: Some(1) };
+        let (output, info) = convert_to_csv(&path, &options).unwrap();
+        assert_eq!(output, converted_path(&path));
+        assert_eq!(source_path(&output), Some(path.clone()));
+        assert_eq!(info["columns"], json!(["id", "column_2", "id_2"]));
+        assert_eq!(info["empty_rows_dropped"], 1);
+
+        let table = Table::from_path(&output).unwrap();
+        assert_eq!(table.rows, vec![vec!["1", "2.5", "true"], vec!["3", "", ""]]);
+    }
+
+    #[test]
+    fn unknown_sheets_and_header_rows_are_rejected() {
+        let path = workbook("spreadsheet_invalid");
+        let missing = SpreadsheetOptions { sheet: Some("summary".to_string()), ..Default::default() };
+        assert!(convert_to_csv(&path, &missing).unwrap_err().to_string().contains("no sheet named 'summary'"));
+        let outside = SpreadsheetOptions { header_row: Some(50), ..Default::default() };
+        assert!(convert_to_csv(&path, &outside).unwrap_err().to_string().contains("outside"));
+    }
+
+    #[test]
+    fn parses_cell_ranges() {
+        assert_eq!(parse_range("B3:F200").unwrap(), ((2, 1), (199, 5)));
+        assert_eq!(parse_range("$aa$1:ab2").unwrap(), ((0, 26), (1, 27)));
+        assert!(parse_range("B3").is_err());
+        assert!(parse_range("F3:B1").is_err());
+        assert!(parse_range("A0:B1").is_err());
+        assert!(parse_range("3:B1").is_err());
+    }
+
+    #[test]
+    fn recognises_spreadsheet_extensions() {
+        assert!(is_spreadsheet("upload/Report.XLSX"));
+        assert!(is_spreadsheet("upload/data.ods"));
+        assert!(!is_spreadsheet("upload/data.csv"));
+        assert_eq!(source_path("upload/data.csv"), None);
+    }
+}
@@ -0,0 +1,244 @@
+use libsqlite3_sys::{sqlite3_set_authorizer, SQLITE_DENY, SQLITE_FUNCTION, SQLITE_OK, SQLITE_READ, SQLITE_RECURSIVE, SQLITE_SELECT};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::sqlite::SqliteConnection;
+use sqlx::{Column, Connection, Executor, Row, TypeInfo, ValueRef};
+use std::error::Error;
+use std::os::raw::{c_char, c_int, c_void};
+
+use crate::nodes::runtime;
+use crate::nodes::table::{is_missing, Table};
+
+pub struct Sql_Query;
+
+#[derive(Deserialize)]
+struct SqlQueryConfig {
+    query: String,
+    // Extra names for the inputs in `neighbors_dependent` order, each input is always available as `node_<id>`
+    #[serde(default)]
+    table_names: Vec<String>,
+}
+
+impl Sql_Query {
+    // Load every input into an in-memory SQLite database and run the query against it
+    pub fn process_node(&self, inputs: &[(u32, &String)], data: &str, node_id: &u32) -> Result<(serde_json::Value, String), Box<dyn Error>> {
+        let config: SqlQueryConfig = serde_json::from_str(data)?;
+        let query = select_statement(&config.query)?;
+
+        let mut tables: Vec<(Vec<String>, Table)> = Vec::new();
+        for (position, (input_id, path)) in inputs.iter().enumerate() {
+            let mut names = vec![format!("node_{}", input_id)];
+            if let Some(alias) = config.table_names.get(position) {
+                names.push(alias.clone());
+            }
+            tables.push((names, Table::from_path(path)?));
+        }
+
+        let result = runtime::block_on(run_query(&tables, query))??;
+
+        let output_file = format!("./storage_bin/sql_{}.csv", node_id);
+        result.write_to_path(&output_file)?;
+        println!("SQL result written to {}", output_file);
+
+        Ok((json!({ "rows": result.rows.len(), "columns": result.headers }), output_file))
+    }
+}
+
+// One SELECT or WITH statement. Quotes and comments are skipped when looking for a second statement;
+// what the statement may do once running is limited by `read_only_authorizer`.
+fn select_statement(query: &str) -> Result<&str, Box<dyn Error>> {
+    let bytes = query.as_bytes();
+    let mut end = bytes.len();
+    let mut i = 0;
+    while i < bytes.len() {
+        let skip_to = |from: usize, close: &str| query[from..].find(close).map(|p| from + p + close.len()).unwrap_or(bytes.len());
+        i = match bytes[i] {
+            b'\'' => skip_to(i + 1, "'"),
+            b'"' => skip_to(i + 1, "\""),
+            b'`' => skip_to(i + 1, "`"),
+            b'[' => skip_to(i + 1, "]"),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => skip_to(i + 2, "\n"),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => skip_to(i + 2, "*/"),
+            b';' => {
+                if !query[i + 1..].trim().is_empty() {
+                    return Err("Only a single statement is allowed".into());
+                }
+                end = i;
+                break;
+            }
+            _ => i + 1,
+        };
+    }
+
+    let statement = query[..end].trim();
+    if statement.is_empty() {
+        return Err("The SQL query is empty".into());
+    }
+    let keyword = statement
+        .trim_start_matches('(')
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or("")
+        .to_lowercase();
+    if keyword != "select" && keyword != "with" {
+        return Err(format!("Only SELECT and WITH queries are allowed, got '{}'", keyword).into());
+    }
+    Ok(statement)
+}
+
+// SQLite asks this for every action a statement takes while it is prepared. Only reading is
+// allowed, so ATTACH, PRAGMA and writes hidden in a CTE are refused before anything runs.
+unsafe extern "C" fn read_only_authorizer(
+    _: *mut c_void,
+    action: c_int,
+    _: *const c_char,
+    _: *const c_char,
+    _: *const c_char,
+    _: *const c_char,
+) -> c_int {
+    match action {
+        SQLITE_SELECT | SQLITE_READ | SQLITE_FUNCTION | SQLITE_RECURSIVE => SQLITE_OK,
+        _ => SQLITE_DENY,
+    }
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+async fn run_query(tables: &[(Vec<String>, Table)], query: &str) -> Result<Table, Box<dyn Error>> {
+    let mut connection = SqliteConnection::connect("sqlite::memory:").await?;
+
+    for (names, table) in tables {
+        let name = quote_identifier(&names[0]);
+        let columns: Vec<String> = table.headers.iter().map(|h| quote_identifier(h)).collect();
+        sqlx::query(&format!("CREATE TABLE {} ({})", name, columns.join(", ")))
+            .execute(&mut connection)
+            .await?;
+
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let insert = format!("INSERT INTO {} VALUES ({})", name, placeholders);
+        let mut transaction = connection.begin().await?;
+        for row in &table.rows {
+            let mut statement = sqlx::query(&insert);
+            // Bind numbers as numbers so comparisons and aggregates behave, missing cells as NULL
+            for cell in row.iter().chain(std::iter::repeat(&String::new())).take(columns.len()) {
+                statement = if is_missing(cell) {
+                    statement.bind(None::<String>)
+                } else if let Ok(integer) = cell.trim().parse::<i64>() {
+                    statement.bind(integer)
+                } else if let Ok(real) = cell.trim().parse::<f64>() {
+                    statement.bind(real)
+                } else {
+                    statement.bind(cell.clone())
+                };
+            }
+            statement.execute(&mut *transaction).await?;
+        }
+        transaction.commit().await?;
+
+        for alias in names.iter().skip(1) {
+            sqlx::query(&format!("CREATE VIEW {} AS SELECT * FROM {}", quote_identifier(alias), name))
+                .execute(&mut connection)
+                .await?;
+        }
+    }
+
+    {
+        let mut handle = connection.lock_handle().await?;
+        // SAFETY: the handle is locked, so the connection worker is not using it, and the callback is a
+        // plain function that keeps no state
+        unsafe { sqlite3_set_authorizer(handle.as_raw_handle().as_ptr(), Some(read_only_authorizer), std::ptr::null_mut()) };
+    }
+    let rows = sqlx::query(query).fetch_all(&mut connection).await?;
+    let headers: Vec<String> = match rows.first() {
+        Some(row) => row.columns().iter().map(|c| c.name().to_string()).collect(),
+        None => {
+            // No rows, still report the column names of the statement
+            let described = connection.describe(query).await?;
+            described.columns().iter().map(|c| c.name().to_string()).collect()
+        }
+    };
+
+    let mut output = Table { headers, rows: Vec::new() };
+    for row in &rows {
+        let mut cells: Vec<String> = Vec::with_capacity(row.len());
+        for i in 0..row.len() {
+            let raw = row.try_get_raw(i)?;
+            let cell = if raw.is_null() {
+                String::new()
+            } else {
+                match raw.type_info().name() {
+                    "INTEGER" | "BOOLEAN" => row.try_get::<i64, _>(i)?.to_string(),
+                    "REAL" | "NUMERIC" => row.try_get::<f64, _>(i)?.to_string(),
+                    "BLOB" => format!("<{} bytes>", row.try_get::<Vec<u8>, _>(i)?.len()),
+                    _ => row.try_get::<String, _>(i)?,
+                }
+            };
+            cells.push(cell);
+        }
+        output.rows.push(cells);
+    }
+
+    connection.close().await?;
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::{read_output, storage_bin, write_csv};
+
+    fn run(file: &str, query: &str, node_id: u32) -> Result<String, Box<dyn Error>> {
+        let path = file.to_string();
+        let data = json!({ "query": query, "table_names": ["sales"] }).to_string();
+        Sql_Query.process_node(&[(1, &path)], &data, &node_id).map(|(_, output)| output)
+    }
+
+    #[test]
+    fn queries_inputs_by_node_id_and_alias() {
+        storage_bin();
+        let file = write_csv("sql_sales", "region,amount\nnorth,10\nsouth,5\nnorth,2.5\n");
+        let rows = read_output(run(&file, "SELECT region, SUM(amount) AS total FROM sales GROUP BY region ORDER BY region;", 900_034));
+        assert_eq!(rows, vec![vec!["region", "total"], vec!["north", "12.5"], vec!["south", "5"]]);
+        assert!(run(&file, "WITH n AS (SELECT * FROM node_1) SELECT COUNT(*) FROM n", 900_035).is_ok());
+        // No rows still reports the columns
+        let empty = read_output(run(&file, "SELECT region FROM sales WHERE amount > 100", 900_037));
+        assert_eq!(empty, vec![vec!["region"]]);
+    }
+
+    // The way `process_nodes` runs nodes, on a blocking thread of a single-threaded server runtime
+    #[tokio::test]
+    async fn runs_on_a_blocking_thread_of_a_current_thread_runtime() {
+        storage_bin();
+        let file = write_csv("sql_blocking", "x\n1\n2\n");
+        let output = tokio::task::spawn_blocking(move || run(&file, "SELECT SUM(x) AS total FROM sales", 900_038).map_err(|e| e.to_string()))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(read_output(Ok(output)), vec![vec!["total"], vec!["3"]]);
+    }
+
+    #[test]
+    fn only_single_select_statements_are_accepted() {
+        assert!(select_statement("SELECT 1;").is_ok());
+        assert!(select_statement("SELECT ';' AS x -- trailing; comment").is_ok());
+        assert!(select_statement("SELECT 1; DROP TABLE sales").is_err());
+        assert!(select_statement("ATTACH DATABASE './users.db' AS u").is_err());
+        assert!(select_statement("PRAGMA table_info(sales)").is_err());
+        assert!(select_statement("  ").is_err());
+    }
+
+    #[test]
+    fn attach_and_writes_are_refused() {
+        storage_bin();
+        let file = write_csv("sql_refused", "a\n1\n");
+        let attached = std::env::temp_dir().join(format!("istat_attach_{}.db", std::process::id()));
+        let attach = format!("SELECT * FROM node_1; ATTACH '{}' AS u", attached.display());
+        assert!(run(&file, &attach, 900_036).is_err());
+        let hidden_write = run(&file, "WITH x AS (SELECT 1) DELETE FROM node_1", 900_036);
+        assert!(hidden_write.unwrap_err().to_string().contains("not authorized"));
+        assert!(run(&file, "SELECT * FROM pragma_table_info('node_1')", 900_036).is_err());
+        assert!(!attached.exists());
+    }
+}
@@ -0,0 +1,302 @@
+use nalgebra::{DMatrix, DVector};
+use serde::Deserialize;
+use serde_json::json;
+use statrs::distribution::{ChiSquared, ContinuousCDF, Normal};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use crate::nodes::table::{is_missing, parse_number, Table};
+
+pub struct Kaplan_Meier;
+
+#[derive(Deserialize)]
+struct KaplanMeierConfig {
+    time_column: String,
+    event_column: String,
+    #[serde(default)]
+    group_column: Option<String>,
+    #[serde(default)]
+    event_value: Option<String>, // Value marking an observed event, 1/true/yes when not set
+    #[serde(default = "default_confidence_level")]
+    confidence_level: f64,
+}
+
+fn default_confidence_level() -> f64 {
+    0.95
+}
+
+struct Observation {
+    time: f64,
+    event: bool,
+}
+
+impl Kaplan_Meier {
+    // Survival curves per group with log-log confidence bands, plus the log-rank test across groups
+    pub fn process_node(&self, file1: &str, data: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+        let config: KaplanMeierConfig = serde_json::from_str(data)?;
+        if config.confidence_level <= 0.0 || config.confidence_level >= 1.0 {
+            return Err("confidence_level must be between 0 and 1".into());
+        }
+
+        let table = Table::from_path(file1)?;
+        let time_index = table.column_index(&config.time_column)?;
+        let event_index = table.column_index(&config.event_column)?;
+        let group_index = match &config.group_column {
+            Some(column) => Some(table.column_index(column)?),
+            None => None,
+        };
+
+        let mut groups: BTreeMap<String, Vec<Observation>> = BTreeMap::new();
+        let mut dropped = 0;
+        for row in &table.rows {
+            let time = row.get(time_index).and_then(|v| parse_number(v)).filter(|t| *t >= 0.0);
+            let event = row.get(event_index).and_then(|v| parse_event(v, config.event_value.as_deref()));
+            let group = match group_index {
+                Some(index) => row.get(index).filter(|v| !is_missing(v)).map(|v| v.trim().to_string()),
+                None => Some("all".to_string()),
+            };
+            match (time, event, group) {
+                (Some(time), Some(event), Some(group)) => groups.entry(group).or_default().push(Observation { time, event }),
+                _ => dropped += 1,
+            }
+        }
+        if groups.is_empty() {
+            return Err("No rows with a valid time and event".into());
+        }
+
+        let z = Normal::new(0.0, 1.0)?.inverse_cdf(1.0 - (1.0 - config.confidence_level) / 2.0);
+        let curves: Vec<serde_json::Value> = groups
+            .iter_mut()
+            .map(|(group, observations)| {
+                observations.sort_by(|a, b| a.time.total_cmp(&b.time));
+                survival_curve(group, observations, z)
+            })
+            .collect();
+
+        // The curves are still reported when the test is undefined
+        let log_rank = log_rank_test(&groups)?;
+
+        Ok(json!({
+            "confidence_level": config.confidence_level,
+            "n_dropped": dropped,
+            "curves": curves,
+            "log_rank": log_rank,
+        }))
+    }
+}
+
+fn parse_event(value: &str, event_value: Option<&str>) -> Option<bool> {
+    if is_missing(value) {
+        return None;
+    }
+    let value = value.trim();
+    if let Some(event_value) = event_value {
+        return Some(value == event_value);
+    }
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+// Kaplan-Meier estimate with Greenwood variance on the log(-log S) scale
+fn survival_curve(group: &str, observations: &[Observation], z: f64) -> serde_json::Value {
+    let mut steps = vec![json!({
+        "time": 0.0,
+        "n_risk": observations.len(),
+        "n_event": 0,
+        "n_censored": 0,
+        "survival": 1.0,
+        "ci_lower": 1.0,
+        "ci_upper": 1.0,
+    })];
+
+    let mut survival = 1.0;
+    let mut greenwood_sum = 0.0;
+    let mut at_risk = observations.len();
+    let mut median: Option<f64> = None;
+    let mut i = 0;
+    while i < observations.len() {
+        let time = observations[i].time;
+        let mut events = 0;
+        let mut censored = 0;
+        while i < observations.len() && observations[i].time == time {
+            if observations[i].event {
+                events += 1;
+            } else {
+                censored += 1;
+            }
+            i += 1;
+        }
+
+        if events > 0 {
+            let n = at_risk as f64;
+            let d = events as f64;
+            survival *= 1.0 - d / n;
+            if n > d {
+                greenwood_sum += d / (n * (n - d));
+            }
+            if median.is_none() && survival <= 0.5 {
+                median = Some(time);
+            }
+        }
+
+        let (ci_lower, ci_upper) = if survival > 0.0 && survival < 1.0 {
+            let log_survival = survival.ln();
+            let se = greenwood_sum.sqrt() / log_survival.abs();
+            let center = (-log_survival).ln();
+            ((-(center + z * se).exp()).exp(), (-(center - z * se).exp()).exp())
+        } else {
+            (survival, survival)
+        };
+
+        steps.push(json!({
+            "time": time,
+            "n_risk": at_risk,
+            "n_event": events,
+            "n_censored": censored,
+            "survival": survival,
+            "ci_lower": ci_lower,
+            "ci_upper": ci_upper,
+        }));
+        at_risk -= events + censored;
+    }
+
+    json!({
+        "group": group,
+        "n": observations.len(),
+        "n_events": observations.iter().filter(|o| o.event).count(),
+        "median_survival": median,
+        "steps": steps,
+    })
+}
+
+// Log-rank test for k groups, chi-square with k - 1 degrees of freedom. `None` when the test is
+// undefined, e.g. no group has an event so the variance matrix is singular.
+fn log_rank_test(groups: &BTreeMap<String, Vec<Observation>>) -> Result<Option<serde_json::Value>, Box<dyn Error>> {
+    let k = groups.len();
+    if k < 2 {
+        return Ok(None);
+    }
+    // Walk every observation once in time order, carrying the number still at risk in each group
+    let mut timeline: Vec<(f64, bool, usize)> = groups
+        .values()
+        .enumerate()
+        .flat_map(|(g, observations)| observations.iter().map(move |o| (o.time, o.event, g)))
+        .collect();
+    timeline.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut at_risk: Vec<f64> = groups.values().map(|o| o.len() as f64).collect();
+
+    let mut observed = vec![0.0; k];
+    let mut expected = vec![0.0; k];
+    let mut variance = DMatrix::<f64>::zeros(k, k);
+    let mut i = 0;
+    while i < timeline.len() {
+        let time = timeline[i].0;
+        let mut events = vec![0.0; k];
+        let mut leaving = vec![0.0; k];
+        while i < timeline.len() && timeline[i].0 == time {
+            let (_, event, g) = timeline[i];
+            if event {
+                events[g] += 1.0;
+            }
+            leaving[g] += 1.0;
+            i += 1;
+        }
+
+        let n: f64 = at_risk.iter().sum();
+        let d: f64 = events.iter().sum();
+        if d > 0.0 && n >= 2.0 {
+            for g in 0..k {
+                observed[g] += events[g];
+                expected[g] += d * at_risk[g] / n;
+                for h in 0..k {
+                    let delta = if g == h { 1.0 } else { 0.0 };
+                    variance[(g, h)] += d * (n - d) / (n - 1.0) * at_risk[g] / n * (delta - at_risk[h] / n);
+                }
+            }
+        }
+        for g in 0..k {
+            at_risk[g] -= leaving[g];
+        }
+    }
+
+    // Drop the last group, the full covariance matrix is singular
+    let difference = DVector::from_iterator(k - 1, (0..k - 1).map(|g| observed[g] - expected[g]));
+    let reduced = variance.view((0, 0), (k - 1, k - 1)).clone_owned();
+    let Some(inverse) = reduced.try_inverse() else {
+        return Ok(None);
+    };
+    let statistic = (difference.transpose() * inverse * &difference)[(0, 0)];
+    if !statistic.is_finite() {
+        return Ok(None);
+    }
+    let df = (k - 1) as f64;
+
+    let per_group: Vec<serde_json::Value> = groups
+        .keys()
+        .enumerate()
+        .map(|(g, group)| json!({ "group": group, "observed": observed[g], "expected": expected[g] }))
+        .collect();
+
+    Ok(Some(json!({
+        "statistic": statistic,
+        "df": df,
+        "p_value": 1.0 - ChiSquared::new(df)?.cdf(statistic),
+        "groups": per_group,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observations(data: &[(f64, bool)]) -> Vec<Observation> {
+        data.iter().map(|(time, event)| Observation { time: *time, event: *event }).collect()
+    }
+
+    #[test]
+    fn product_limit_estimate_with_censoring() {
+        // Events at 1 and 3, censored at 2: S(1) = 3/4, S(3) = 3/4 * 1/2
+        let data = observations(&[(1.0, true), (2.0, false), (3.0, true), (4.0, false)]);
+        let curve = survival_curve("all", &data, 1.96);
+        let survival: Vec<f64> = curve["steps"].as_array().unwrap().iter().map(|s| s["survival"].as_f64().unwrap()).collect();
+        assert_eq!(survival, vec![1.0, 0.75, 0.75, 0.375, 0.375]);
+        assert_eq!(curve["median_survival"], 3.0);
+        let lower = curve["steps"][1]["ci_lower"].as_f64().unwrap();
+        assert!(lower > 0.0 && lower < 0.75);
+    }
+
+    #[test]
+    fn log_rank_two_groups_matches_hand_computation() {
+        // At t=1: n=4 (2, 2), one event in a. At t=2: n=3 (1, 2), one event in b.
+        // O - E for a = (1 - 1/2) + (0 - 1/3) = 1/6, V = 1/4 + 2/9 = 17/36, statistic = (1/36) / (17/36)
+        let mut groups = BTreeMap::new();
+        groups.insert("a".to_string(), observations(&[(1.0, true), (3.0, false)]));
+        groups.insert("b".to_string(), observations(&[(2.0, true), (3.0, false)]));
+        let test = log_rank_test(&groups).unwrap().unwrap();
+        assert!((test["statistic"].as_f64().unwrap() - 1.0 / 17.0).abs() < 1e-12);
+        assert_eq!(test["df"], 1.0);
+    }
+
+    #[test]
+    fn log_rank_without_events_is_null() {
+        let mut groups = BTreeMap::new();
+        groups.insert("a".to_string(), observations(&[(1.0, false), (2.0, false)]));
+        groups.insert("b".to_string(), observations(&[(3.0, false)]));
+        assert!(log_rank_test(&groups).unwrap().is_none());
+
+        let mut single = BTreeMap::new();
+        single.insert("a".to_string(), observations(&[(1.0, true)]));
+        assert!(log_rank_test(&single).unwrap().is_none());
+    }
+
+    #[test]
+    fn event_values() {
+        assert_eq!(parse_event("Yes", None), Some(true));
+        assert_eq!(parse_event("0", None), Some(false));
+        assert_eq!(parse_event("dead", Some("dead")), Some(true));
+        assert_eq!(parse_event("maybe", None), None);
+    }
+}
@@ -0,0 +1,212 @@
+use csv::WriterBuilder;
+use std::cmp::Ordering;
+use std::error::Error;
+
+use crate::nodes::dialect::open_reader;
+
+// In-memory copy of a CSV file, shared by the nodes that need random access to rows
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn from_path(file_path: &str) -> Result<Table, Box<dyn Error>> {
+        let mut reader = open_reader(file_path)?;
+
+        let headers: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        for result in reader.records() {
+            let record = result?;
+            rows.push(record.iter().map(|v| v.to_string()).collect());
+        }
+
+        Ok(Table { headers, rows })
+    }
+
+    pub fn column_index(&self, column: &str) -> Result<usize, Box<dyn Error>> {
+        self.headers
+            .iter()
+            .position(|h| h == column)
+            .ok_or_else(|| format!("Table does not contain a column named '{}'", column).into())
+    }
+
+    // Parse a column as numbers, missing or non-numeric cells become `None`
+    pub fn numeric_column(&self, index: usize) -> Vec<Option<f64>> {
+        self.rows
+            .iter()
+            .map(|row| row.get(index).and_then(|v| parse_number(v)))
+            .collect()
+    }
+
+    // Rows where every listed column is numeric, with their positions in the table
+    pub fn complete_numeric_rows(&self, indices: &[usize]) -> (Vec<usize>, Vec<Vec<f64>>) {
+        let mut positions: Vec<usize> = Vec::new();
+        let mut rows: Vec<Vec<f64>> = Vec::new();
+        for (position, row) in self.rows.iter().enumerate() {
+            let values: Option<Vec<f64>> = indices.iter().map(|i| row.get(*i).and_then(|v| parse_number(v))).collect();
+            if let Some(values) = values {
+                positions.push(position);
+                rows.push(values);
+            }
+        }
+        (positions, rows)
+    }
+
+    // Append computed columns, cells of rows not listed in `row_indices` are left empty
+    pub fn append_columns(&mut self, names: &[&str], row_indices: &[usize], columns: &[Vec<String>]) {
+        for name in names {
+            self.headers.push(name.to_string());
+        }
+        for row in self.rows.iter_mut() {
+            row.extend(names.iter().map(|_| String::new()));
+        }
+        let first = self.headers.len() - names.len();
+        for (i, row_index) in row_indices.iter().enumerate() {
+            for (offset, column) in columns.iter().enumerate() {
+                self.rows[*row_index][first + offset] = column[i].clone();
+            }
+        }
+    }
+
+    pub fn write_to_path(&self, file_path: &str) -> Result<(), Box<dyn Error>> {
+        let mut writer = WriterBuilder::new().from_path(file_path)?;
+        writer.write_record(&self.headers)?;
+        for row in &self.rows {
+            writer.write_record(row)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+// Same definition of a missing cell as `Clean_By_Column`
+pub fn is_missing(value: &str) -> bool {
+    value == "NA" || value.trim().is_empty()
+}
+
+pub fn parse_number(value: &str) -> Option<f64> {
+    if is_missing(value) {
+        return None;
+    }
+    value.trim().parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+// Total order for sorting cells: every number before every non-number, numbers by value and text
+// by bytes. Comparing a number with text as text would not be transitive ("9" < "10" < "5a" < "9").
+pub fn compare_values(a: &str, b: &str) -> Ordering {
+    match (parse_number(a), parse_number(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+// Locale-aware variant, with `decimal_comma` "1.234,5" reads as 1234.5
+pub fn parse_localized_number(value: &str, decimal_comma: bool) -> Option<f64> {
+    if !decimal_comma {
+        return parse_number(value);
+    }
+    normalize_localized_number(value, decimal_comma)?.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+// Rewrite a localized number with a `.` decimal point and no grouping, so it can be parsed as either
+// an integer or a float. Group separators are only accepted between groups of three digits, so with
+// `decimal_comma` "1.5" is rejected instead of being read as 15.
+pub fn normalize_localized_number(value: &str, decimal_comma: bool) -> Option<String> {
+    if is_missing(value) {
+        return None;
+    }
+    let trimmed = value.trim();
+    if !decimal_comma {
+        return Some(trimmed.to_string());
+    }
+    let (integer, fraction) = match trimmed.split_once(',') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (trimmed, None),
+    };
+    let is_separator = |c: char| c == '.' || c == ' ' || c == '\u{a0}';
+    let digits = integer.trim_start_matches(['-', '+']);
+    if digits.contains(is_separator) {
+        let mut groups = digits.split(is_separator);
+        let leading = groups.next().unwrap_or_default().len();
+        if !(1..=3).contains(&leading) || !groups.all(|g| g.len() == 3) {
+            return None;
+        }
+    }
+
+    let mut normalized = integer[..integer.len() - digits.len()].to_string();
+    normalized.extend(digits.chars().filter(|c| !is_separator(*c)));
+    if let Some(fraction) = fraction {
+        normalized.push('.');
+        normalized.push_str(fraction);
+    }
+    Some(normalized)
+}
+
+#[cfg(test)]
+pub mod test_support {
+    use std::path::PathBuf;
+
+    // Write a CSV fixture to a scratch directory; `name` must be unique per test since tests run in parallel
+    pub fn write_csv(name: &str, contents: &str) -> String {
+        let directory: PathBuf = std::env::temp_dir().join(format!("istat_tests_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(format!("{}.csv", name));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    // Header and rows of the CSV a node wrote
+    pub fn read_output(node_output: Result<String, Box<dyn std::error::Error>>) -> Vec<Vec<String>> {
+        let path = node_output.unwrap();
+        let mut reader = csv::Reader::from_path(path).unwrap();
+        let mut rows = vec![reader.headers().unwrap().iter().map(|v| v.to_string()).collect()];
+        rows.extend(reader.records().map(|r| r.unwrap().iter().map(|v| v.to_string()).collect()));
+        rows
+    }
+
+    // Nodes write their outputs to `./storage_bin`, which is otherwise only created at runtime
+    pub fn storage_bin() {
+        std::fs::create_dir_all("./storage_bin").unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal_comma_accepts_thousands_groups() {
+        assert_eq!(parse_localized_number("1.234,5", true), Some(1234.5));
+        assert_eq!(parse_localized_number("-1.234.567", true), Some(-1234567.0));
+        assert_eq!(parse_localized_number("12\u{a0}345,25", true), Some(12345.25));
+        assert_eq!(parse_localized_number("0,5", true), Some(0.5));
+    }
+
+    #[test]
+    fn decimal_comma_rejects_dots_outside_group_positions() {
+        assert_eq!(parse_localized_number("1.5", true), None);
+        assert_eq!(parse_localized_number("1.23,4", true), None);
+        assert_eq!(parse_localized_number("1234.567", true), None);
+        assert_eq!(parse_localized_number("1,234.5", true), None);
+        assert_eq!(parse_localized_number(".123", true), None);
+    }
+
+    #[test]
+    fn numbers_sort_before_text() {
+        let mut cells = vec!["abc", "10", "5a", "9", "-1.5", "B"];
+        cells.sort_by(|a, b| compare_values(a, b));
+        assert_eq!(cells, vec!["-1.5", "9", "10", "5a", "B", "abc"]);
+        assert_eq!(compare_values("2.0", "2"), Ordering::Equal);
+    }
+
+    #[test]
+    fn point_decimals_are_unchanged() {
+        assert_eq!(parse_localized_number("1.5", false), Some(1.5));
+        assert_eq!(parse_localized_number("NA", false), None);
+        assert_eq!(parse_localized_number(" ", true), None);
+        assert_eq!(normalize_localized_number(" 42 ", false).as_deref(), Some("42"));
+    }
+}
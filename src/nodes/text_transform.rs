@@ -0,0 +1,367 @@
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+use serde_json::json;
+use std::error::Error;
+
+use crate::nodes::table::{is_missing, Table};
+
+pub struct Text_Transform;
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+enum Side {
+    #[default]
+    Both,
+    Left,
+    Right,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "operation", rename_all = "kebab-case")]
+enum TextOperation {
+    Trim {
+        #[serde(default)]
+        side: Side,
+        #[serde(default)]
+        collapse_whitespace: bool, // Also squeeze inner runs of whitespace to one space
+    },
+    Lowercase,
+    Uppercase,
+    TitleCase,
+    Replace {
+        find: String,
+        #[serde(default)]
+        replace: String, // `$1`/`${name}` refer to capture groups when `regex` is set
+        #[serde(default)]
+        regex: bool,
+        #[serde(default)]
+        case_insensitive: bool,
+    },
+    Extract {
+        pattern: String,
+        #[serde(default)]
+        names: Option<Vec<String>>, // Output column per capture group, named groups or `<column>_<n>` when not set
+    },
+    Split {
+        delimiter: String,
+        #[serde(default)]
+        names: Option<Vec<String>>, // Output columns, the number of parts in the widest row when not set
+    },
+    Pad {
+        width: usize,
+        #[serde(default = "default_fill")]
+        fill: char,
+        #[serde(default = "default_pad_side")]
+        side: Side,
+    },
+}
+
+fn default_fill() -> char {
+    ' '
+}
+
+fn default_pad_side() -> Side {
+    Side::Left
+}
+
+#[derive(Deserialize)]
+struct TextStep {
+    column: String,
+    #[serde(default)]
+    output: Option<String>, // Defaults to replacing the column in place, ignored by `extract` and `split`
+    #[serde(flatten)]
+    operation: TextOperation,
+}
+
+#[derive(Deserialize)]
+struct TextTransformConfig {
+    steps: Vec<TextStep>,
+}
+
+impl Text_Transform {
+    // Apply each string operation in order, missing cells are left untouched
+    pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<(serde_json::Value, String), Box<dyn Error>> {
+        let config: TextTransformConfig = serde_json::from_str(data)?;
+        let mut table = Table::from_path(file1)?;
+
+        let mut summaries: Vec<serde_json::Value> = Vec::new();
+        for step in &config.steps {
+            let index = table.column_index(&step.column)?;
+            let cells: Vec<Option<String>> = table
+                .rows
+                .iter()
+                .map(|row| row.get(index).filter(|v| !is_missing(v)).cloned())
+                .collect();
+
+            match &step.operation {
+                TextOperation::Extract { pattern, names } => {
+                    let regex = Regex::new(pattern)?;
+                    let groups = regex.captures_len() - 1;
+                    if groups == 0 {
+                        return Err(format!("Pattern '{}' has no capture groups to extract", pattern).into());
+                    }
+                    let names = output_names(names, &step.column, groups, |i| regex.capture_names().nth(i).flatten())?;
+                    let mut matched = 0;
+                    let mut columns = vec![Vec::with_capacity(cells.len()); groups];
+                    for cell in &cells {
+                        let captures = cell.as_deref().and_then(|c| regex.captures(c));
+                        matched += captures.is_some() as usize;
+                        for (group, column) in columns.iter_mut().enumerate() {
+                            let value = captures.as_ref().and_then(|c| c.get(group + 1)).map(|m| m.as_str());
+                            column.push(value.unwrap_or("").to_string());
+                        }
+                    }
+                    append_all(&mut table, &names, &columns);
+                    summaries.push(json!({ "column": step.column, "operation": "extract", "matched": matched, "columns": names }));
+                }
+                TextOperation::Split { delimiter, names } => {
+                    if delimiter.is_empty() {
+                        return Err("The split delimiter is empty".into());
+                    }
+                    let parts: Vec<Vec<&str>> = cells
+                        .iter()
+                        .map(|cell| match (cell, names) {
+                            // With fixed names, the last column keeps the rest of the value
+                            (Some(c), Some(names)) => c.splitn(names.len().max(1), delimiter.as_str()).collect(),
+                            (Some(c), None) => c.split(delimiter.as_str()).collect(),
+                            (None, _) => Vec::new(),
+                        })
+                        .collect();
+                    let width = match names {
+                        Some(names) => names.len(),
+                        None => parts.iter().map(|p| p.len()).max().unwrap_or(0),
+                    };
+                    let names = output_names(names, &step.column, width, |_| None)?;
+                    let columns: Vec<Vec<String>> = (0..width)
+                        .map(|i| parts.iter().map(|p| p.get(i).map(|v| v.trim().to_string()).unwrap_or_default()).collect())
+                        .collect();
+                    append_all(&mut table, &names, &columns);
+                    summaries.push(json!({ "column": step.column, "operation": "split", "columns": names }));
+                }
+                operation => {
+                    let regex = match operation {
+                        TextOperation::Replace { find, regex: true, case_insensitive, .. } => {
+                            Some(RegexBuilder::new(find).case_insensitive(*case_insensitive).build()?)
+                        }
+                        TextOperation::Replace { find, regex: false, case_insensitive: true, .. } => {
+                            Some(RegexBuilder::new(&regex::escape(find)).case_insensitive(true).build()?)
+                        }
+                        _ => None,
+                    };
+                    let mut changed = 0;
+                    let converted: Vec<String> = table
+                        .rows
+                        .iter()
+                        .zip(cells.iter())
+                        .map(|(row, cell)| match cell {
+                            Some(cell) => {
+                                let value = apply_cell(cell, operation, regex.as_ref());
+                                changed += (value != *cell) as usize;
+                                value
+                            }
+                            None => row.get(index).cloned().unwrap_or_default(),
+                        })
+                        .collect();
+
+                    match &step.output {
+                        Some(output) if output != &step.column => append_all(&mut table, std::slice::from_ref(output), &[converted]),
+                        _ => {
+                            for (row, value) in table.rows.iter_mut().zip(converted) {
+                                if let Some(cell) = row.get_mut(index) {
+                                    *cell = value;
+                                }
+                            }
+                        }
+                    }
+                    summaries.push(json!({ "column": step.column, "operation": operation_name(operation), "changed": changed }));
+                }
+            }
+        }
+
+        let output_file = format!("./storage_bin/text_{}.csv", node_id);
+        table.write_to_path(&output_file)?;
+        println!("Text transform written to {}", output_file);
+
+        Ok((json!({ "steps": summaries, "rows": table.rows.len() }), output_file))
+    }
+}
+
+fn operation_name(operation: &TextOperation) -> &'static str {
+    match operation {
+        TextOperation::Trim { .. } => "trim",
+        TextOperation::Lowercase => "lowercase",
+        TextOperation::Uppercase => "uppercase",
+        TextOperation::TitleCase => "title-case",
+        TextOperation::Replace { .. } => "replace",
+        TextOperation::Extract { .. } => "extract",
+        TextOperation::Split { .. } => "split",
+        TextOperation::Pad { .. } => "pad",
+    }
+}
+
+// Use the requested names, else the fallback name for the position, else `<column>_<n>`
+fn output_names<'a>(
+    names: &Option<Vec<String>>,
+    column: &str,
+    count: usize,
+    fallback: impl Fn(usize) -> Option<&'a str>,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    match names {
+        Some(names) if names.len() != count => {
+            Err(format!("Expected {} output names for '{}', got {}", count, column, names.len()).into())
+        }
+        Some(names) => Ok(names.clone()),
+        None => Ok((0..count)
+            .map(|i| fallback(i + 1).map(|n| n.to_string()).unwrap_or_else(|| format!("{}_{}", column, i + 1)))
+            .collect()),
+    }
+}
+
+fn append_all(table: &mut Table, names: &[String], columns: &[Vec<String>]) {
+    let name_refs: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+    let all_rows: Vec<usize> = (0..table.rows.len()).collect();
+    table.append_columns(&name_refs, &all_rows, columns);
+}
+
+fn apply_cell(cell: &str, operation: &TextOperation, regex: Option<&Regex>) -> String {
+    match operation {
+        TextOperation::Trim { side, collapse_whitespace } => {
+            let trimmed = match side {
+                Side::Both => cell.trim(),
+                Side::Left => cell.trim_start(),
+                Side::Right => cell.trim_end(),
+            };
+            if *collapse_whitespace {
+                let mut collapsed = String::with_capacity(trimmed.len());
+                let mut previous_space = false;
+                for c in trimmed.chars() {
+                    if c.is_whitespace() {
+                        if !previous_space {
+                            collapsed.push(' ');
+                        }
+                        previous_space = true;
+                    } else {
+                        collapsed.push(c);
+                        previous_space = false;
+                    }
+                }
+                collapsed
+            } else {
+                trimmed.to_string()
+            }
+        }
+        TextOperation::Lowercase => cell.to_lowercase(),
+        TextOperation::Uppercase => cell.to_uppercase(),
+        TextOperation::TitleCase => {
+            let mut titled = String::with_capacity(cell.len());
+            let mut word_start = true;
+            for c in cell.chars() {
+                if word_start {
+                    titled.extend(c.to_uppercase());
+                } else {
+                    titled.extend(c.to_lowercase());
+                }
+                word_start = !c.is_alphanumeric() && c != '\'';
+            }
+            titled
+        }
+        TextOperation::Replace { find, replace, regex: is_regex, .. } => match regex {
+            Some(regex) if *is_regex => regex.replace_all(cell, replace.as_str()).into_owned(),
+            // Case-insensitive literal match, the replacement is taken literally too
+            Some(regex) => regex.replace_all(cell, regex::NoExpand(replace)).into_owned(),
+            None => cell.replace(find.as_str(), replace),
+        },
+        TextOperation::Pad { width, fill, side } => {
+            let length = cell.chars().count();
+            if length >= *width {
+                return cell.to_string();
+            }
+            let padding = width - length;
+            let repeat = |n: usize| std::iter::repeat_n(*fill, n).collect::<String>();
+            match side {
+                Side::Left => format!("{}{}", repeat(padding), cell),
+                Side::Right => format!("{}{}", cell, repeat(padding)),
+                Side::Both => format!("{}{}{}", repeat(padding / 2), cell, repeat(padding - padding / 2)),
+            }
+        }
+        TextOperation::Extract { .. } | TextOperation::Split { .. } => cell.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::{read_output, storage_bin, write_csv};
+
+    fn run(name: &str, node_id: u32, contents: &str, steps: &str) -> Vec<Vec<String>> {
+        storage_bin();
+        let file = write_csv(name, contents);
+        read_output(Text_Transform.process_node(&file, &format!(r#"{{"steps": {}}}"#, steps), &node_id).map(|(_, path)| path))
+    }
+
+    fn cell(operation: &str, value: &str) -> String {
+        let operation: TextOperation = serde_json::from_str(operation).unwrap();
+        apply_cell(value, &operation, None)
+    }
+
+    #[test]
+    fn case_trim_and_pad() {
+        assert_eq!(cell(r#"{"operation": "trim", "collapse_whitespace": true}"#, "  a   b \t c "), "a b c");
+        assert_eq!(cell(r#"{"operation": "trim", "side": "left"}"#, "  a  "), "a  ");
+        assert_eq!(cell(r#"{"operation": "title-case"}"#, "o'neil VAN-dyke"), "O'neil Van-Dyke");
+        assert_eq!(cell(r#"{"operation": "pad", "width": 5, "fill": "0"}"#, "42"), "00042");
+        assert_eq!(cell(r#"{"operation": "pad", "width": 5, "side": "both", "fill": "*"}"#, "ab"), "*ab**");
+        assert_eq!(cell(r#"{"operation": "pad", "width": 2}"#, "long"), "long");
+    }
+
+    #[test]
+    fn replace_literal_and_regex() {
+        let rows = run(
+            "text_replace",
+            900_050,
+            "code\nA-1.5\na-2.5\n",
+            r#"[{"column": "code", "operation": "replace", "find": "a-", "replace": "$0", "case_insensitive": true},
+                {"column": "code", "output": "digits", "operation": "replace", "find": "(\\d)\\.(\\d)", "replace": "$2$1", "regex": true}]"#,
+        );
+        assert_eq!(rows[1], vec!["$01.5", "$051"]);
+        assert_eq!(rows[2], vec!["$02.5", "$052"]);
+    }
+
+    #[test]
+    fn extract_uses_named_groups_and_leaves_misses_empty() {
+        let rows = run(
+            "text_extract",
+            900_051,
+            "id\nAB-12\nnone\nNA\n",
+            r#"[{"column": "id", "operation": "extract", "pattern": "(?P<prefix>[A-Z]+)-(\\d+)"}]"#,
+        );
+        assert_eq!(rows[0], vec!["id", "prefix", "id_2"]);
+        assert_eq!(rows[1], vec!["AB-12", "AB", "12"]);
+        assert_eq!(rows[2], vec!["none", "", ""]);
+        assert_eq!(rows[3], vec!["NA", "", ""]);
+    }
+
+    #[test]
+    fn split_with_fixed_names_keeps_the_rest_in_the_last_column() {
+        let rows = run(
+            "text_split",
+            900_052,
+            "name\n\"Doe, Jane, Q\"\nSmith\n",
+            r#"[{"column": "name", "operation": "split", "delimiter": ",", "names": ["last", "rest"]}]"#,
+        );
+        assert_eq!(rows[1][1..], ["Doe", "Jane, Q"]);
+        assert_eq!(rows[2][1..], ["Smith", ""]);
+    }
+
+    #[test]
+    fn rejects_patterns_without_groups_and_mismatched_names() {
+        storage_bin();
+        let file = write_csv("text_invalid", "x\na\n");
+        let error = |steps: &str| {
+            Text_Transform.process_node(&file, &format!(r#"{{"steps": {}}}"#, steps), &900_060).err().unwrap().to_string()
+        };
+        assert!(error(r#"[{"column": "x", "operation": "extract", "pattern": "a"}]"#).contains("no capture groups"));
+        assert!(error(r#"[{"column": "x", "operation": "extract", "pattern": "(a)", "names": ["p", "q"]}]"#).contains("Expected 1"));
+        assert!(error(r#"[{"column": "x", "operation": "split", "delimiter": ""}]"#).contains("empty"));
+    }
+}
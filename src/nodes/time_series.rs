@@ -0,0 +1,498 @@
+use chrono::{Datelike, Duration, Months, NaiveDateTime, Timelike};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::error::Error;
+
+use crate::nodes::datetime::{parse_datetime, OUTPUT_FORMAT};
+use crate::nodes::descriptive::mean;
+use crate::nodes::table::{parse_number, Table};
+
+pub struct Time_Series;
+
+// Resampling writes one row per bucket, empty ones included
+const MAX_BUCKETS: usize = 1_000_000;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Frequency {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Aggregation {
+    Mean,
+    Sum,
+    Min,
+    Max,
+    Count,
+    First,
+    Last,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum GapFill {
+    None,
+    Zero,
+    Forward,
+    Linear,
+}
+
+#[derive(Deserialize)]
+struct ResampleConfig {
+    frequency: Frequency,
+    columns: Vec<String>,
+    #[serde(default = "default_aggregation")]
+    aggregation: Aggregation,
+    #[serde(default = "default_fill")]
+    fill: GapFill, // How empty buckets are filled
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum WindowFunction {
+    Mean,
+    Sum,
+    Std,
+    Min,
+    Max,
+}
+
+#[derive(Deserialize)]
+struct WindowConfig {
+    column: String,
+    function: WindowFunction,
+    #[serde(default)]
+    window: Option<usize>, // Rolling window size in rows, expanding window when not set
+    #[serde(default)]
+    output: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum ShiftKind {
+    Lag,
+    Lead,
+    Diff,
+}
+
+#[derive(Deserialize)]
+struct ShiftConfig {
+    column: String,
+    kind: ShiftKind,
+    #[serde(default = "default_periods")]
+    periods: usize,
+    #[serde(default)]
+    output: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TimeSeriesConfig {
+    time_column: String,
+    #[serde(default)]
+    format: Option<String>, // chrono format string, common formats are detected when not set
+    #[serde(default)]
+    resample: Option<ResampleConfig>,
+    #[serde(default)]
+    windows: Vec<WindowConfig>,
+    #[serde(default)]
+    shifts: Vec<ShiftConfig>,
+}
+
+fn default_aggregation() -> Aggregation {
+    Aggregation::Mean
+}
+
+fn default_fill() -> GapFill {
+    GapFill::None
+}
+
+fn default_periods() -> usize {
+    1
+}
+
+impl Time_Series {
+    // Sort by the parsed time column, optionally resample to a fixed frequency, then add window and shift columns
+    pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<(serde_json::Value, String), Box<dyn Error>> {
+        let config: TimeSeriesConfig = serde_json::from_str(data)?;
+        let table = Table::from_path(file1)?;
+        let time_index = table.column_index(&config.time_column)?;
+
+        let mut timed: Vec<(NaiveDateTime, Vec<String>)> = Vec::new();
+        for row in &table.rows {
+            if let Some(time) = row.get(time_index).and_then(|v| parse_datetime(v, config.format.as_deref())) {
+                timed.push((time, row.clone()));
+            }
+        }
+        let unparsed_rows = table.rows.len() - timed.len();
+        if timed.is_empty() {
+            return Err(format!("No values in '{}' could be parsed as dates", config.time_column).into());
+        }
+        timed.sort_by_key(|(time, _)| *time);
+
+        let mut output = match &config.resample {
+            Some(resample) => resample_rows(&table, time_index, &config.time_column, &timed, resample)?,
+            None => Table {
+                headers: table.headers.clone(),
+                rows: timed.into_iter().map(|(_, row)| row).collect(),
+            },
+        };
+        let all_rows: Vec<usize> = (0..output.rows.len()).collect();
+
+        for window in &config.windows {
+            let values = output.numeric_column(output.column_index(&window.column)?);
+            let computed = rolling(&values, window.window, window.function);
+            let name = window.output.clone().unwrap_or_else(|| {
+                let function = match window.function {
+                    WindowFunction::Mean => "mean",
+                    WindowFunction::Sum => "sum",
+                    WindowFunction::Std => "std",
+                    WindowFunction::Min => "min",
+                    WindowFunction::Max => "max",
+                };
+                match window.window {
+                    Some(size) => format!("{}_rolling_{}_{}", window.column, function, size),
+                    None => format!("{}_expanding_{}", window.column, function),
+                }
+            });
+            output.append_columns(&[name.as_str()], &all_rows, &[format_values(&computed)]);
+        }
+
+        for shift in &config.shifts {
+            let values = output.numeric_column(output.column_index(&shift.column)?);
+            let n = values.len();
+            let periods = shift.periods;
+            let computed: Vec<Option<f64>> = (0..n)
+                .map(|i| match shift.kind {
+                    ShiftKind::Lag => i.checked_sub(periods).and_then(|j| values[j]),
+                    ShiftKind::Lead => values.get(i + periods).copied().flatten(),
+                    ShiftKind::Diff => i.checked_sub(periods).and_then(|j| Some(values[i]? - values[j]?)),
+                })
+                .collect();
+            let name = shift.output.clone().unwrap_or_else(|| {
+                let kind = match shift.kind {
+                    ShiftKind::Lag => "lag",
+                    ShiftKind::Lead => "lead",
+                    ShiftKind::Diff => "diff",
+                };
+                format!("{}_{}_{}", shift.column, kind, periods)
+            });
+            output.append_columns(&[name.as_str()], &all_rows, &[format_values(&computed)]);
+        }
+
+        let output_file = format!("./storage_bin/time_series_{}.csv", node_id);
+        output.write_to_path(&output_file)?;
+        println!("Time series written to {}", output_file);
+
+        Ok((
+            json!({
+                "rows": output.rows.len(),
+                "unparsed_rows": unparsed_rows,
+                "columns": output.headers,
+            }),
+            output_file,
+        ))
+    }
+}
+
+fn format_values(values: &[Option<f64>]) -> Vec<String> {
+    values.iter().map(|v| v.map(|v| v.to_string()).unwrap_or_default()).collect()
+}
+
+// Start of the bucket containing `time`, weeks start on Monday
+fn bucket_start(time: NaiveDateTime, frequency: Frequency) -> NaiveDateTime {
+    let date = time.date();
+    match frequency {
+        Frequency::Minute => date.and_hms_opt(time.hour(), time.minute(), 0),
+        Frequency::Hour => date.and_hms_opt(time.hour(), 0, 0),
+        Frequency::Day => date.and_hms_opt(0, 0, 0),
+        Frequency::Week => (date - Duration::days(date.weekday().num_days_from_monday() as i64)).and_hms_opt(0, 0, 0),
+        Frequency::Month => date.with_day(1).and_then(|d| d.and_hms_opt(0, 0, 0)),
+    }
+    .unwrap_or(time)
+}
+
+fn next_bucket(start: NaiveDateTime, frequency: Frequency) -> NaiveDateTime {
+    match frequency {
+        Frequency::Minute => start + Duration::minutes(1),
+        Frequency::Hour => start + Duration::hours(1),
+        Frequency::Day => start + Duration::days(1),
+        Frequency::Week => start + Duration::weeks(1),
+        Frequency::Month => start.checked_add_months(Months::new(1)).unwrap_or(start + Duration::days(31)),
+    }
+}
+
+fn resample_rows(
+    table: &Table,
+    time_index: usize,
+    time_column: &str,
+    timed: &[(NaiveDateTime, Vec<String>)],
+    config: &ResampleConfig,
+) -> Result<Table, Box<dyn Error>> {
+    let mut indices: Vec<usize> = Vec::new();
+    for column in &config.columns {
+        let index = table.column_index(column)?;
+        if index == time_index {
+            return Err("The time column cannot also be aggregated".into());
+        }
+        indices.push(index);
+    }
+
+    // Group the sorted rows into consecutive buckets, inserting empty buckets for gaps
+    let mut buckets: Vec<(NaiveDateTime, Vec<&Vec<String>>)> = Vec::new();
+    let mut current = bucket_start(timed[0].0, config.frequency);
+    let last = bucket_start(timed[timed.len() - 1].0, config.frequency);
+    let mut position = 0;
+    while current <= last {
+        if buckets.len() == MAX_BUCKETS {
+            return Err(format!("Resampling would produce more than {} rows, use a coarser frequency", MAX_BUCKETS).into());
+        }
+        let next = next_bucket(current, config.frequency);
+        let mut members: Vec<&Vec<String>> = Vec::new();
+        while position < timed.len() && timed[position].0 < next {
+            members.push(&timed[position].1);
+            position += 1;
+        }
+        buckets.push((current, members));
+        current = next;
+    }
+
+    let mut columns: Vec<Vec<Option<f64>>> = Vec::new();
+    for index in &indices {
+        let mut aggregated: Vec<Option<f64>> = buckets
+            .iter()
+            .map(|(_, members)| {
+                let values: Vec<f64> = members.iter().filter_map(|row| row.get(*index).and_then(|v| parse_number(v))).collect();
+                aggregate(&values, config.aggregation)
+            })
+            .collect();
+        fill_gaps(&mut aggregated, config.fill);
+        columns.push(aggregated);
+    }
+
+    let mut headers = vec![time_column.to_string()];
+    headers.extend(config.columns.iter().cloned());
+    let rows = buckets
+        .iter()
+        .enumerate()
+        .map(|(i, (start, _))| {
+            let mut row = vec![start.format(OUTPUT_FORMAT).to_string()];
+            row.extend(columns.iter().map(|column| column[i].map(|v| v.to_string()).unwrap_or_default()));
+            row
+        })
+        .collect();
+
+    Ok(Table { headers, rows })
+}
+
+fn aggregate(values: &[f64], aggregation: Aggregation) -> Option<f64> {
+    if values.is_empty() {
+        return match aggregation {
+            Aggregation::Count => Some(0.0),
+            _ => None,
+        };
+    }
+    Some(match aggregation {
+        Aggregation::Mean => mean(values),
+        Aggregation::Sum => values.iter().sum(),
+        Aggregation::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+        Aggregation::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        Aggregation::Count => values.len() as f64,
+        Aggregation::First => values[0],
+        Aggregation::Last => values[values.len() - 1],
+    })
+}
+
+fn fill_gaps(values: &mut [Option<f64>], fill: GapFill) {
+    match fill {
+        GapFill::None => {}
+        GapFill::Zero => values.iter_mut().filter(|v| v.is_none()).for_each(|v| *v = Some(0.0)),
+        GapFill::Forward => {
+            let mut previous = None;
+            for value in values.iter_mut() {
+                match value {
+                    Some(v) => previous = Some(*v),
+                    None => *value = previous,
+                }
+            }
+        }
+        GapFill::Linear => {
+            // Interpolate between the nearest known buckets, leading and trailing gaps stay empty
+            let known: Vec<usize> = (0..values.len()).filter(|i| values[*i].is_some()).collect();
+            for pair in known.windows(2) {
+                let (start, end) = (pair[0], pair[1]);
+                let (a, b) = (values[start].unwrap_or(0.0), values[end].unwrap_or(0.0));
+                for (offset, value) in values[(start + 1)..end].iter_mut().enumerate() {
+                    let fraction = (offset + 1) as f64 / (end - start) as f64;
+                    *value = Some(a + (b - a) * fraction);
+                }
+            }
+        }
+    }
+}
+
+// Rolling results need a full window of rows, missing values inside the window are skipped.
+// Each row updates running state instead of rescanning the window, so both kinds are O(n).
+fn rolling(values: &[Option<f64>], window: Option<usize>, function: WindowFunction) -> Vec<Option<f64>> {
+    if window == Some(0) {
+        return vec![None; values.len()];
+    }
+    let mut moments = Moments::default();
+    // Positions of candidate extremes, their values monotonic from front to back
+    let mut extremes: VecDeque<usize> = VecDeque::new();
+    let keep_front = |candidate: f64, newer: f64| match function {
+        WindowFunction::Max => candidate > newer,
+        _ => candidate < newer,
+    };
+
+    (0..values.len())
+        .map(|i| {
+            if let Some(value) = values[i] {
+                moments.add(value);
+                while extremes.back().is_some_and(|j| !keep_front(values[*j].unwrap_or_default(), value)) {
+                    extremes.pop_back();
+                }
+                extremes.push_back(i);
+            }
+            let start = match window {
+                Some(size) if i + 1 < size => return None,
+                Some(size) => i + 1 - size,
+                None => 0,
+            };
+            if start > 0 {
+                if let Some(leaving) = values[start - 1] {
+                    moments.remove(leaving);
+                }
+                while extremes.front().is_some_and(|j| *j < start) {
+                    extremes.pop_front();
+                }
+            }
+            if moments.count == 0 {
+                return None;
+            }
+            let result = match function {
+                WindowFunction::Mean => moments.mean,
+                WindowFunction::Sum => moments.mean * moments.count as f64,
+                WindowFunction::Std if moments.count < 2 => f64::NAN,
+                WindowFunction::Std => (moments.m2.max(0.0) / (moments.count - 1) as f64).sqrt(),
+                WindowFunction::Min | WindowFunction::Max => values[*extremes.front()?]?,
+            };
+            Some(result).filter(|v| v.is_finite())
+        })
+        .collect()
+}
+
+// Welford's running mean and sum of squared deviations, with removal for sliding windows
+#[derive(Default)]
+struct Moments {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn remove(&mut self, value: f64) {
+        if self.count <= 1 {
+            *self = Moments::default();
+            return;
+        }
+        let delta = value - self.mean;
+        self.count -= 1;
+        self.mean -= delta / self.count as f64;
+        self.m2 -= delta * (value - self.mean);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::descriptive::std_dev;
+
+    // The window recomputed from scratch, what the running version must reproduce
+    fn brute_force(values: &[Option<f64>], window: Option<usize>, function: WindowFunction) -> Vec<Option<f64>> {
+        (0..values.len())
+            .map(|i| {
+                let start = match window {
+                    Some(size) if i + 1 < size => return None,
+                    Some(size) => i + 1 - size,
+                    None => 0,
+                };
+                let present: Vec<f64> = values[start..=i].iter().flatten().copied().collect();
+                if present.is_empty() {
+                    return None;
+                }
+                let result = match function {
+                    WindowFunction::Mean => mean(&present),
+                    WindowFunction::Sum => present.iter().sum(),
+                    WindowFunction::Std => std_dev(&present),
+                    WindowFunction::Min => present.iter().copied().fold(f64::INFINITY, f64::min),
+                    WindowFunction::Max => present.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                };
+                Some(result).filter(|v| v.is_finite())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn running_windows_match_recomputation() {
+        let values = vec![Some(3.0), Some(1.0), None, Some(4.0), Some(1.0), Some(5.0), None, None, None, Some(9.0), Some(2.0), Some(6.0)];
+        let functions = [WindowFunction::Mean, WindowFunction::Sum, WindowFunction::Std, WindowFunction::Min, WindowFunction::Max];
+        for window in [None, Some(1), Some(2), Some(3), Some(5)] {
+            for function in functions {
+                let running = rolling(&values, window, function);
+                let expected = brute_force(&values, window, function);
+                for (a, b) in running.iter().zip(&expected) {
+                    match (a, b) {
+                        (Some(a), Some(b)) => assert!((a - b).abs() < 1e-9),
+                        _ => assert_eq!(a.is_none(), b.is_none()),
+                    }
+                }
+            }
+        }
+    }
+
+    fn timed(times: &[&str], values: &[&str]) -> (Table, Vec<(NaiveDateTime, Vec<String>)>) {
+        let table = Table {
+            headers: vec!["time".to_string(), "value".to_string()],
+            rows: times.iter().zip(values).map(|(t, v)| vec![t.to_string(), v.to_string()]).collect(),
+        };
+        let timed = table.rows.iter().map(|row| (parse_datetime(&row[0], None).unwrap(), row.clone())).collect();
+        (table, timed)
+    }
+
+    #[test]
+    fn resample_fills_gaps_linearly() {
+        let (table, timed) = timed(&["2024-01-01 10:00:00", "2024-01-01 10:30:00", "2024-01-03 09:00:00"], &["1", "3", "8"]);
+        let config: ResampleConfig =
+            serde_json::from_str(r#"{"frequency": "day", "columns": ["value"], "aggregation": "mean", "fill": "linear"}"#).unwrap();
+        let output = resample_rows(&table, 0, "time", &timed, &config).unwrap();
+        let values: Vec<&str> = output.rows.iter().map(|row| row[1].as_str()).collect();
+        assert_eq!(values, vec!["2", "5", "8"]);
+    }
+
+    #[test]
+    fn resample_bucket_count_is_limited() {
+        let (table, timed) = timed(&["2000-01-01 00:00:00", "2024-01-01 00:00:00"], &["1", "2"]);
+        let config: ResampleConfig = serde_json::from_str(r#"{"frequency": "minute", "columns": ["value"]}"#).unwrap();
+        assert!(resample_rows(&table, 0, "time", &timed, &config).is_err());
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        let sunday = parse_datetime("2024-03-10 15:00:00", None).unwrap();
+        assert_eq!(bucket_start(sunday, Frequency::Week).format("%Y-%m-%d").to_string(), "2024-03-04");
+    }
+}
@@ -0,0 +1,401 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use crate::nodes::descriptive::{mean, quantile_sorted, sorted, std_dev};
+use crate::nodes::table::{is_missing, parse_number, Table};
+
+pub struct Column_Transform;
+
+// Fitted parameters are optional in the request; when present they are reused instead of being
+// estimated, which is how a transform fitted on one upload is reapplied to another.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(tag = "transform", rename_all = "kebab-case")]
+enum Transform {
+    Standardize { mean: Option<f64>, std: Option<f64> },
+    MinMax { min: Option<f64>, max: Option<f64> },
+    Robust { median: Option<f64>, iqr: Option<f64> },
+    Log { offset: Option<f64> },
+    BoxCox { lambda: Option<f64> },
+    OneHot { levels: Option<Vec<String>> },
+    Ordinal { levels: Option<Vec<String>> },
+    EqualWidthBins { bins: usize, edges: Option<Vec<f64>> },
+    QuantileBins { bins: usize, edges: Option<Vec<f64>> },
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+struct TransformStep {
+    column: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    output: Option<String>, // Defaults to replacing the column in place
+    #[serde(flatten)]
+    transform: Transform,
+}
+
+// Bin transforms allocate one edge per bin
+const MAX_BINS: usize = 10_000;
+
+#[derive(Deserialize)]
+struct TransformConfig {
+    steps: Vec<TransformStep>,
+}
+
+impl Column_Transform {
+    // Apply each step in order and return the fitted steps, which can be sent back unchanged as `data`
+    pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<(serde_json::Value, String), Box<dyn Error>> {
+        let config: TransformConfig = serde_json::from_str(data)?;
+        let mut table = Table::from_path(file1)?;
+
+        let mut fitted_steps: Vec<TransformStep> = Vec::new();
+        for step in config.steps {
+            let index = table.column_index(&step.column)?;
+            let transform = fit(&table, index, &step)?;
+            apply(&mut table, index, &step, &transform)?;
+            fitted_steps.push(TransformStep { transform, ..step });
+        }
+
+        let output_file = format!("./storage_bin/transform_{}.csv", node_id);
+        table.write_to_path(&output_file)?;
+        println!("Transformed CSV written to {}", output_file);
+
+        Ok((json!({ "steps": fitted_steps, "rows": table.rows.len() }), output_file))
+    }
+}
+
+fn numeric_values(table: &Table, index: usize) -> Vec<f64> {
+    table.numeric_column(index).into_iter().flatten().collect()
+}
+
+fn require_values(values: &[f64], column: &str) -> Result<(), Box<dyn Error>> {
+    if values.is_empty() {
+        return Err(format!("Column '{}' has no numeric values to fit", column).into());
+    }
+    Ok(())
+}
+
+fn check_bins(bins: usize) -> Result<usize, Box<dyn Error>> {
+    if bins > MAX_BINS {
+        return Err(format!("At most {} bins are allowed, got {}", MAX_BINS, bins).into());
+    }
+    Ok(bins.max(1))
+}
+
+// The scaling transforms reuse a fit only when every parameter of it is supplied
+fn partial_parameters(first: &str, second: &str) -> Box<dyn Error> {
+    format!("Supply both `{}` and `{}` to reuse a fit, or neither to fit them from the data", first, second).into()
+}
+
+// A supplied divisor of zero would empty every cell of the column
+fn check_scale(scale: f64, name: &str) -> Result<(), Box<dyn Error>> {
+    if !scale.is_finite() || scale == 0.0 {
+        return Err(format!("`{}` must be a non-zero number, got {}", name, scale).into());
+    }
+    Ok(())
+}
+
+// Fill in the parameters that were not supplied
+fn fit(table: &Table, index: usize, step: &TransformStep) -> Result<Transform, Box<dyn Error>> {
+    let column = &step.column;
+    let transform = match &step.transform {
+        Transform::Standardize { mean: Some(m), std: Some(s) } => {
+            check_scale(*s, "std")?;
+            Transform::Standardize { mean: Some(*m), std: Some(*s) }
+        }
+        Transform::Standardize { mean: None, std: None } => {
+            let values = numeric_values(table, index);
+            require_values(&values, column)?;
+            let std = std_dev(&values);
+            if !std.is_finite() || std == 0.0 {
+                return Err(format!("Column '{}' is constant and cannot be standardized", column).into());
+            }
+            Transform::Standardize { mean: Some(mean(&values)), std: Some(std) }
+        }
+        Transform::Standardize { .. } => return Err(partial_parameters("mean", "std")),
+        Transform::MinMax { min: Some(lo), max: Some(hi) } => {
+            check_scale(hi - lo, "max - min")?;
+            Transform::MinMax { min: Some(*lo), max: Some(*hi) }
+        }
+        Transform::MinMax { min: None, max: None } => {
+            let values = sorted(&numeric_values(table, index));
+            require_values(&values, column)?;
+            let (lo, hi) = (values[0], values[values.len() - 1]);
+            if hi == lo {
+                return Err(format!("Column '{}' is constant and cannot be min-max scaled", column).into());
+            }
+            Transform::MinMax { min: Some(lo), max: Some(hi) }
+        }
+        Transform::MinMax { .. } => return Err(partial_parameters("min", "max")),
+        Transform::Robust { median: Some(m), iqr: Some(i) } => {
+            check_scale(*i, "iqr")?;
+            Transform::Robust { median: Some(*m), iqr: Some(*i) }
+        }
+        Transform::Robust { median: None, iqr: None } => {
+            let values = sorted(&numeric_values(table, index));
+            require_values(&values, column)?;
+            let iqr = quantile_sorted(&values, 0.75) - quantile_sorted(&values, 0.25);
+            if iqr == 0.0 {
+                return Err(format!("Column '{}' has an interquartile range of zero and cannot be robust scaled", column).into());
+            }
+            Transform::Robust { median: Some(quantile_sorted(&values, 0.5)), iqr: Some(iqr) }
+        }
+        Transform::Robust { .. } => return Err(partial_parameters("median", "iqr")),
+        Transform::Log { offset: Some(o) } => Transform::Log { offset: Some(*o) },
+        Transform::Log { offset: None } => {
+            // Shift so the smallest value maps to log(1) when the column is not strictly positive
+            let values = sorted(&numeric_values(table, index));
+            require_values(&values, column)?;
+            Transform::Log { offset: Some(if values[0] > 0.0 { 0.0 } else { 1.0 - values[0] }) }
+        }
+        Transform::BoxCox { lambda: Some(l) } => Transform::BoxCox { lambda: Some(*l) },
+        Transform::BoxCox { lambda: None } => {
+            let values = numeric_values(table, index);
+            require_values(&values, column)?;
+            if values.iter().any(|v| *v <= 0.0) {
+                return Err(format!("Box-Cox needs strictly positive values in '{}'", column).into());
+            }
+            Transform::BoxCox { lambda: Some(box_cox_lambda(&values)) }
+        }
+        Transform::OneHot { levels: Some(levels) } => Transform::OneHot { levels: Some(levels.clone()) },
+        Transform::OneHot { levels: None } => Transform::OneHot { levels: Some(category_levels(table, index)) },
+        Transform::Ordinal { levels: Some(levels) } => Transform::Ordinal { levels: Some(levels.clone()) },
+        Transform::Ordinal { levels: None } => Transform::Ordinal { levels: Some(category_levels(table, index)) },
+        Transform::EqualWidthBins { bins, edges: Some(edges) } => Transform::EqualWidthBins { bins: *bins, edges: Some(edges.clone()) },
+        Transform::EqualWidthBins { bins, edges: None } => {
+            let values = sorted(&numeric_values(table, index));
+            require_values(&values, column)?;
+            let bins = check_bins(*bins)?;
+            let min = values[0];
+            let width = (values[values.len() - 1] - min) / bins as f64;
+            let edges = (0..=bins).map(|i| min + i as f64 * width).collect();
+            Transform::EqualWidthBins { bins, edges: Some(edges) }
+        }
+        Transform::QuantileBins { bins, edges: Some(edges) } => Transform::QuantileBins { bins: *bins, edges: Some(edges.clone()) },
+        Transform::QuantileBins { bins, edges: None } => {
+            let values = sorted(&numeric_values(table, index));
+            require_values(&values, column)?;
+            let bins = check_bins(*bins)?;
+            let edges = (0..=bins).map(|i| quantile_sorted(&values, i as f64 / bins as f64)).collect();
+            Transform::QuantileBins { bins, edges: Some(edges) }
+        }
+    };
+    Ok(transform)
+}
+
+fn apply(table: &mut Table, index: usize, step: &TransformStep, transform: &Transform) -> Result<(), Box<dyn Error>> {
+    if let Transform::OneHot { levels: Some(levels) } = transform {
+        let prefix = step.output.clone().unwrap_or_else(|| step.column.clone());
+        let names: Vec<String> = levels.iter().map(|level| format!("{}_{}", prefix, level)).collect();
+        let name_refs: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+        let all_rows: Vec<usize> = (0..table.rows.len()).collect();
+        let columns: Vec<Vec<String>> = levels
+            .iter()
+            .map(|level| {
+                table
+                    .rows
+                    .iter()
+                    .map(|row| match row.get(index) {
+                        Some(v) if is_missing(v) => String::new(),
+                        Some(v) => if v.trim() == level { "1" } else { "0" }.to_string(),
+                        None => String::new(),
+                    })
+                    .collect()
+            })
+            .collect();
+        table.append_columns(&name_refs, &all_rows, &columns);
+        return Ok(());
+    }
+
+    let converted: Vec<String> = table
+        .rows
+        .iter()
+        .map(|row| {
+            let cell = row.get(index).map(|v| v.as_str()).unwrap_or("");
+            apply_cell(cell, transform).map(|v| v.to_string()).unwrap_or_default()
+        })
+        .collect();
+
+    let target = match &step.output {
+        Some(output) if output != &step.column => {
+            let all_rows: Vec<usize> = (0..table.rows.len()).collect();
+            table.append_columns(&[output.as_str()], &all_rows, &[converted]);
+            return Ok(());
+        }
+        _ => index,
+    };
+    for (row, value) in table.rows.iter_mut().zip(converted) {
+        if let Some(cell) = row.get_mut(target) {
+            *cell = value;
+        }
+    }
+    Ok(())
+}
+
+// Missing and unparseable cells stay empty
+fn apply_cell(cell: &str, transform: &Transform) -> Option<f64> {
+    if let Transform::Ordinal { levels: Some(levels) } = transform {
+        return levels.iter().position(|level| level == cell.trim()).map(|i| i as f64);
+    }
+
+    let x = parse_number(cell)?;
+    let value = match transform {
+        Transform::Standardize { mean: Some(m), std: Some(s) } => (x - m) / s,
+        Transform::MinMax { min: Some(lo), max: Some(hi) } => (x - lo) / (hi - lo),
+        Transform::Robust { median: Some(m), iqr: Some(i) } => (x - m) / i,
+        Transform::Log { offset: Some(o) } => (x + o).ln(),
+        Transform::BoxCox { lambda: Some(l) } => box_cox(x, *l),
+        Transform::EqualWidthBins { edges: Some(edges), .. } | Transform::QuantileBins { edges: Some(edges), .. } => {
+            bin_index(x, edges) as f64
+        }
+        _ => return None,
+    };
+    Some(value).filter(|v| v.is_finite())
+}
+
+fn category_levels(table: &Table, index: usize) -> Vec<String> {
+    table
+        .rows
+        .iter()
+        .filter_map(|row| row.get(index))
+        .filter(|v| !is_missing(v))
+        .map(|v| v.trim().to_string())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+// Values below the first edge fall in bin 0 and above the last edge in the last bin
+fn bin_index(x: f64, edges: &[f64]) -> usize {
+    let bins = edges.len().saturating_sub(1).max(1);
+    let position = edges.iter().skip(1).position(|edge| x < *edge).unwrap_or(bins - 1);
+    position.min(bins - 1)
+}
+
+fn box_cox(x: f64, lambda: f64) -> f64 {
+    if lambda.abs() < 1e-12 {
+        x.ln()
+    } else {
+        (x.powf(lambda) - 1.0) / lambda
+    }
+}
+
+// Maximum likelihood lambda by golden section search over [-5, 5]
+fn box_cox_lambda(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    let log_sum: f64 = values.iter().map(|v| v.ln()).sum();
+    let log_likelihood = |lambda: f64| {
+        let transformed: Vec<f64> = values.iter().map(|v| box_cox(*v, lambda)).collect();
+        let m = mean(&transformed);
+        let variance = transformed.iter().map(|v| (v - m).powi(2)).sum::<f64>() / n;
+        -n / 2.0 * variance.ln() + (lambda - 1.0) * log_sum
+    };
+
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut a, mut b) = (-5.0, 5.0);
+    let mut c = b - ratio * (b - a);
+    let mut d = a + ratio * (b - a);
+    for _ in 0..100 {
+        if log_likelihood(c) > log_likelihood(d) {
+            b = d;
+        } else {
+            a = c;
+        }
+        c = b - ratio * (b - a);
+        d = a + ratio * (b - a);
+    }
+    (a + b) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(values: &[&str]) -> Table {
+        Table {
+            headers: vec!["x".to_string()],
+            rows: values.iter().map(|v| vec![v.to_string()]).collect(),
+        }
+    }
+
+    fn step(json: &str) -> TransformStep {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn column(table: &Table, index: usize) -> Vec<String> {
+        table.rows.iter().map(|row| row[index].clone()).collect()
+    }
+
+    #[test]
+    fn standardize_fits_mean_and_sample_std() {
+        let table = table(&["1", "2", "3", "NA"]);
+        match fit(&table, 0, &step(r#"{"column": "x", "transform": "standardize"}"#)).unwrap() {
+            Transform::Standardize { mean, std } => assert_eq!((mean, std), (Some(2.0), Some(1.0))),
+            _ => panic!("wrong transform"),
+        }
+    }
+
+    #[test]
+    fn supplied_parameters_are_reused() {
+        let mut table = table(&["10", "20", ""]);
+        let step = step(r#"{"column": "x", "transform": "min-max", "min": 0.0, "max": 40.0}"#);
+        let transform = fit(&table, 0, &step).unwrap();
+        apply(&mut table, 0, &step, &transform).unwrap();
+        assert_eq!(column(&table, 0), vec!["0.25", "0.5", ""]);
+    }
+
+    #[test]
+    fn partial_parameters_are_rejected() {
+        let table = table(&["1", "2", "3"]);
+        let error = fit(&table, 0, &step(r#"{"column": "x", "transform": "standardize", "mean": 5.0}"#)).err().unwrap();
+        assert!(error.to_string().contains("both `mean` and `std`"));
+        assert!(fit(&table, 0, &step(r#"{"column": "x", "transform": "min-max", "max": 5.0}"#)).is_err());
+        assert!(fit(&table, 0, &step(r#"{"column": "x", "transform": "robust", "iqr": 2.0}"#)).is_err());
+        assert!(fit(&table, 0, &step(r#"{"column": "x", "transform": "standardize", "mean": 0.0, "std": 0.0}"#)).is_err());
+    }
+
+    #[test]
+    fn constant_columns_cannot_be_scaled() {
+        let table = table(&["4", "4", "NA", "4"]);
+        for transform in ["standardize", "min-max", "robust"] {
+            let step = step(&format!(r#"{{"column": "x", "transform": "{}"}}"#, transform));
+            let error = fit(&table, 0, &step).err().unwrap().to_string();
+            assert!(error.starts_with("Column 'x'"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn one_hot_appends_a_column_per_level() {
+        let mut table = table(&["b", "a", "NA", "b"]);
+        let step = step(r#"{"column": "x", "transform": "one-hot"}"#);
+        let transform = fit(&table, 0, &step).unwrap();
+        apply(&mut table, 0, &step, &transform).unwrap();
+        assert_eq!(table.headers, vec!["x", "x_a", "x_b"]);
+        assert_eq!(column(&table, 1), vec!["0", "1", "", "0"]);
+        assert_eq!(column(&table, 2), vec!["1", "0", "", "1"]);
+    }
+
+    #[test]
+    fn bins_clamp_values_outside_the_edges() {
+        let edges = [0.0, 1.0, 2.0, 3.0];
+        assert_eq!(bin_index(-5.0, &edges), 0);
+        assert_eq!(bin_index(1.0, &edges), 1);
+        assert_eq!(bin_index(3.0, &edges), 2);
+        assert_eq!(bin_index(99.0, &edges), 2);
+    }
+
+    #[test]
+    fn too_many_bins_are_rejected() {
+        let table = table(&["1", "2"]);
+        let step = step(r#"{"column": "x", "transform": "equal-width-bins", "bins": 1000000000000}"#);
+        assert!(fit(&table, 0, &step).is_err());
+    }
+
+    #[test]
+    fn box_cox_lambda_of_one_is_a_shift() {
+        assert_eq!(box_cox(5.0, 1.0), 4.0);
+        assert!((box_cox(std::f64::consts::E, 0.0) - 1.0).abs() < 1e-12);
+        // Exponential growth is made linear by the log, so lambda lands near zero
+        let values: Vec<f64> = (0..50).map(|i| (i as f64 / 10.0).exp()).collect();
+        assert!(box_cox_lambda(&values).abs() < 0.2);
+    }
+}
@@ -0,0 +1,278 @@
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::nodes::table::{compare_values, is_missing, parse_number, Table};
+
+pub struct Window_Functions;
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum WindowFunction {
+    RowNumber,
+    Rank,
+    DenseRank,
+    PercentRank,
+    Ntile,
+    CumulativeSum,
+    CumulativeMean,
+    Lag,
+    Lead,
+}
+
+#[derive(Deserialize)]
+struct OrderBy {
+    column: String,
+    #[serde(default)]
+    descending: bool,
+}
+
+#[derive(Deserialize)]
+struct FunctionConfig {
+    function: WindowFunction,
+    #[serde(default)]
+    column: Option<String>, // Required by the cumulative functions and lag/lead
+    #[serde(default)]
+    n: Option<usize>, // Bucket count for ntile, offset for lag/lead
+    #[serde(default)]
+    output: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WindowConfig {
+    #[serde(default)]
+    partition_by: Vec<String>,
+    #[serde(default)]
+    order_by: Vec<OrderBy>,
+    functions: Vec<FunctionConfig>,
+}
+
+impl Window_Functions {
+    // SQL style window functions, every input row is kept in its original position
+    pub fn process_node(&self, file1: &str, data: &str, node_id: &u32) -> Result<String, Box<dyn Error>> {
+        let config: WindowConfig = serde_json::from_str(data)?;
+        let mut table = Table::from_path(file1)?;
+
+        let partition_indices = config
+            .partition_by
+            .iter()
+            .map(|c| table.column_index(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        let order_indices = config
+            .order_by
+            .iter()
+            .map(|o| Ok((table.column_index(&o.column)?, o.descending)))
+            .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+        // Row positions of each partition, in first-seen order, sorted by the order keys
+        let mut partitions: Vec<Vec<usize>> = Vec::new();
+        let mut partition_lookup: HashMap<Vec<&str>, usize> = HashMap::new();
+        for (row_index, row) in table.rows.iter().enumerate() {
+            let key: Vec<&str> = partition_indices.iter().map(|i| row.get(*i).map(|v| v.as_str()).unwrap_or("")).collect();
+            let slot = *partition_lookup.entry(key).or_insert_with(|| {
+                partitions.push(Vec::new());
+                partitions.len() - 1
+            });
+            partitions[slot].push(row_index);
+        }
+        let compare = |a: &usize, b: &usize| -> Ordering {
+            for (index, descending) in &order_indices {
+                let ordering = compare_cells(&table.rows[*a][*index], &table.rows[*b][*index], *descending);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            Ordering::Equal
+        };
+        for partition in partitions.iter_mut() {
+            partition.sort_by(compare); // Stable, so ties keep their input order
+        }
+        // Whether each row ties with the previous row of its partition on every order key
+        let peers: Vec<Vec<bool>> = partitions
+            .iter()
+            .map(|partition| {
+                (0..partition.len())
+                    .map(|p| p > 0 && compare(&partition[p - 1], &partition[p]) == Ordering::Equal)
+                    .collect()
+            })
+            .collect();
+
+        let all_rows: Vec<usize> = (0..table.rows.len()).collect();
+        for function in &config.functions {
+            let mut values = vec![String::new(); table.rows.len()];
+            let source = match &function.column {
+                Some(column) => Some(table.column_index(column)?),
+                None => None,
+            };
+
+            for (partition, peers) in partitions.iter().zip(peers.iter()) {
+                let size = partition.len();
+                let mut rank = 0;
+                let mut dense_rank = 0;
+                let mut running_sum = 0.0;
+                let mut running_count = 0;
+
+                for (position, row_index) in partition.iter().enumerate() {
+                    if !peers[position] {
+                        rank = position + 1;
+                        dense_rank += 1;
+                    }
+
+                    values[*row_index] = match function.function {
+                        WindowFunction::RowNumber => (position + 1).to_string(),
+                        WindowFunction::Rank => rank.to_string(),
+                        WindowFunction::DenseRank => dense_rank.to_string(),
+                        WindowFunction::PercentRank => {
+                            if size > 1 {
+                                ((rank - 1) as f64 / (size - 1) as f64).to_string()
+                            } else {
+                                "0".to_string()
+                            }
+                        }
+                        WindowFunction::Ntile => {
+                            let buckets = function.n.filter(|n| *n > 0).ok_or("ntile needs a positive `n`")?;
+                            ntile(position, size, buckets).to_string()
+                        }
+                        WindowFunction::CumulativeSum | WindowFunction::CumulativeMean => {
+                            let index = source.ok_or("Cumulative functions need a `column`")?;
+                            if let Some(value) = parse_number(&table.rows[*row_index][index]) {
+                                running_sum += value;
+                                running_count += 1;
+                            }
+                            match function.function {
+                                WindowFunction::CumulativeSum => running_sum.to_string(),
+                                _ if running_count > 0 => (running_sum / running_count as f64).to_string(),
+                                _ => String::new(),
+                            }
+                        }
+                        WindowFunction::Lag | WindowFunction::Lead => {
+                            let index = source.ok_or("lag and lead need a `column`")?;
+                            let offset = function.n.unwrap_or(1);
+                            let other = match function.function {
+                                WindowFunction::Lag => position.checked_sub(offset),
+                                _ => Some(position + offset).filter(|p| *p < size),
+                            };
+                            other.map(|p| table.rows[partition[p]][index].clone()).unwrap_or_default()
+                        }
+                    };
+                }
+            }
+
+            let name = function.output.clone().unwrap_or_else(|| default_name(function));
+            table.append_columns(&[name.as_str()], &all_rows, &[values]);
+        }
+
+        let output_file = format!("./storage_bin/window_{}.csv", node_id);
+        table.write_to_path(&output_file)?;
+        println!("Window functions written to {}", output_file);
+        Ok(output_file)
+    }
+}
+
+// Numbers before text (see `compare_values`), missing values always sort last
+fn compare_cells(a: &str, b: &str, descending: bool) -> Ordering {
+    match (is_missing(a), is_missing(b)) {
+        (true, true) => return Ordering::Equal,
+        (true, false) => return Ordering::Greater,
+        (false, true) => return Ordering::Less,
+        _ => {}
+    }
+    let ordering = compare_values(a, b);
+    if descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+// Same distribution as SQL NTILE: the first `size % buckets` buckets get one extra row
+fn ntile(position: usize, size: usize, buckets: usize) -> usize {
+    let base = size / buckets;
+    let remainder = size % buckets;
+    let large = remainder * (base + 1);
+    if position < large {
+        position / (base + 1) + 1
+    } else {
+        remainder + (position - large) / base.max(1) + 1
+    }
+}
+
+fn default_name(function: &FunctionConfig) -> String {
+    let column = function.column.as_deref().unwrap_or("");
+    match function.function {
+        WindowFunction::RowNumber => "row_number".to_string(),
+        WindowFunction::Rank => "rank".to_string(),
+        WindowFunction::DenseRank => "dense_rank".to_string(),
+        WindowFunction::PercentRank => "percent_rank".to_string(),
+        WindowFunction::Ntile => format!("ntile_{}", function.n.unwrap_or(0)),
+        WindowFunction::CumulativeSum => format!("{}_cumulative_sum", column),
+        WindowFunction::CumulativeMean => format!("{}_cumulative_mean", column),
+        WindowFunction::Lag => format!("{}_lag_{}", column, function.n.unwrap_or(1)),
+        WindowFunction::Lead => format!("{}_lead_{}", column, function.n.unwrap_or(1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::table::test_support::{read_output, storage_bin, write_csv};
+
+    fn column(rows: &[Vec<String>], name: &str) -> Vec<String> {
+        let index = rows[0].iter().position(|h| h == name).unwrap();
+        rows[1..].iter().map(|row| row[index].clone()).collect()
+    }
+
+    #[test]
+    fn ranks_and_running_sums_within_partitions() {
+        storage_bin();
+        let file = write_csv("window_partitions", "team,score\na,10\nb,5\na,30\na,10\nb,NA\n");
+        let data = r#"{
+            "partition_by": ["team"],
+            "order_by": [{"column": "score", "descending": true}],
+            "functions": [
+                {"function": "rank"},
+                {"function": "dense_rank"},
+                {"function": "row_number"},
+                {"function": "cumulative_sum", "column": "score"},
+                {"function": "lag", "column": "score"}
+            ]
+        }"#;
+        let rows = read_output(Window_Functions.process_node(&file, data, &900_033));
+
+        // Rows stay in input order; `a` sorts 30, 10, 10 and the missing `b` score sorts last
+        assert_eq!(column(&rows, "rank"), vec!["2", "1", "1", "2", "2"]);
+        assert_eq!(column(&rows, "dense_rank"), vec!["2", "1", "1", "2", "2"]);
+        assert_eq!(column(&rows, "row_number"), vec!["2", "1", "1", "3", "2"]);
+        assert_eq!(column(&rows, "score_cumulative_sum"), vec!["40", "5", "30", "50", "5"]);
+        assert_eq!(column(&rows, "score_lag_1"), vec!["30", "", "", "10", "5"]);
+    }
+
+    #[test]
+    fn ntile_gives_early_buckets_the_extra_rows() {
+        let buckets: Vec<usize> = (0..7).map(|p| ntile(p, 7, 3)).collect();
+        assert_eq!(buckets, vec![1, 1, 1, 2, 2, 3, 3]);
+        let more_buckets_than_rows: Vec<usize> = (0..2).map(|p| ntile(p, 2, 5)).collect();
+        assert_eq!(more_buckets_than_rows, vec![1, 2]);
+    }
+
+    #[test]
+    fn missing_values_sort_last_either_way() {
+        assert_eq!(compare_cells("NA", "1", false), Ordering::Greater);
+        assert_eq!(compare_cells("NA", "1", true), Ordering::Greater);
+        assert_eq!(compare_cells("10", "9", false), Ordering::Greater);
+    }
+
+    #[test]
+    fn mixed_columns_sort_numbers_before_text() {
+        storage_bin();
+        let file = write_csv("window_mixed", "code\n9\n10\n5a\nabc\nNA\n");
+        let data = r#"{"order_by": [{"column": "code"}], "functions": [{"function": "row_number"}]}"#;
+        let rows = read_output(Window_Functions.process_node(&file, data, &900_034));
+        assert_eq!(column(&rows, "row_number"), vec!["1", "2", "3", "4", "5"]);
+
+        let data = r#"{"order_by": [{"column": "code", "descending": true}], "functions": [{"function": "row_number"}]}"#;
+        let rows = read_output(Window_Functions.process_node(&file, data, &900_035));
+        assert_eq!(column(&rows, "row_number"), vec!["4", "3", "2", "1", "5"]);
+    }
+}